@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use serenity::futures::lock::Mutex;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::news::Article;
+use crate::storage::{load_json, save_json};
+
+/// An announced article as it's kept in the archive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedPatch {
+    pub gid: String,
+    pub title: String,
+    pub url: String,
+    /// Unix timestamp of when the article was published.
+    pub date: i64,
+    pub contents: String,
+    pub contents_hash: String,
+    /// The `news_maxlength` the contents were fetched with, if known. Contents cut to a
+    /// different length can't be compared for edits.
+    #[serde(default)]
+    pub maxlength: Option<u32>,
+}
+
+impl ArchivedPatch {
+    pub fn new(article: &Article, maxlength: u32) -> Self {
+        Self {
+            gid: article.gid.clone(),
+            title: article.title.clone(),
+            url: article.url.clone(),
+            date: article.date.timestamp(),
+            contents: article.contents.clone(),
+            contents_hash: contents_hash(&article.contents),
+            maxlength: Some(maxlength),
+        }
+    }
+}
+
+/// The archive as shared between the update checker and commands.
+pub type SharedArchive = Arc<Mutex<PatchArchive>>;
+
+/// Every article the bot has announced, newest first, saved to a JSON file so the history
+/// survives restarts.
+#[derive(Debug, Default)]
+pub struct PatchArchive {
+    path: Option<PathBuf>,
+    patches: Vec<ArchivedPatch>,
+}
+
+impl PatchArchive {
+    /// An archive that isn't saved anywhere.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn shared(self) -> SharedArchive {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Loads the archive saved at `path`, or starts an empty one there if it doesn't exist.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let patches = load_json(&path, "patch archive")?;
+        let mut archive = Self {
+            path: Some(path),
+            patches,
+        };
+        archive.sort();
+        Ok(archive)
+    }
+
+    /// Adds `articles`, fetched with `news_maxlength` set to `maxlength`, to the archive,
+    /// replacing any already archived under the same gid, and saves it.
+    pub fn record(&mut self, articles: &[Article], maxlength: u32) -> Result<(), String> {
+        for article in articles {
+            let patch = ArchivedPatch::new(article, maxlength);
+            match self.patches.iter_mut().find(|p| p.gid == patch.gid) {
+                Some(existing) => *existing = patch,
+                None => self.patches.push(patch),
+            }
+        }
+        self.sort();
+        self.save()
+    }
+
+    pub fn get(&self, gid: &str) -> Option<&ArchivedPatch> {
+        self.patches.iter().find(|patch| patch.gid == gid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.patches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    /// The `page`th (from 1) group of `per_page` patches, newest first.
+    pub fn page(&self, page: usize, per_page: usize) -> &[ArchivedPatch] {
+        let start = page.saturating_sub(1).saturating_mul(per_page);
+        let end = start.saturating_add(per_page).min(self.patches.len());
+        self.patches.get(start..end).unwrap_or_default()
+    }
+
+    pub fn page_count(&self, per_page: usize) -> usize {
+        self.patches.len().div_ceil(per_page).max(1)
+    }
+
+    /// Up to `limit` patches matching `query`, best first. Titles count for more than
+    /// contents, and words only loosely matching a title (their letters in order) still count
+    /// a little, so typos like "733b" find "7.33b".
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&ArchivedPatch> {
+        let query = query.trim().to_lowercase();
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let mut scored: Vec<(u32, &ArchivedPatch)> = self
+            .patches
+            .iter()
+            .filter_map(|patch| {
+                let title = patch.title.to_lowercase();
+                let contents = patch.contents.to_lowercase();
+                let mut score = if title == query {
+                    200
+                } else if title.contains(&query) {
+                    100
+                } else {
+                    0
+                };
+                for term in &terms {
+                    if title.contains(term) {
+                        score += 10;
+                    } else if contents.contains(term) {
+                        score += 3;
+                    } else if is_subsequence(term, &title) {
+                        score += 1;
+                    }
+                }
+                (score > 0).then_some((score, patch))
+            })
+            .collect();
+        // Patches are already newest first, and the sort is stable
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, patch)| patch)
+            .collect()
+    }
+
+    fn sort(&mut self) {
+        self.patches
+            .sort_by(|a, b| b.date.cmp(&a.date).then_with(|| b.gid.cmp(&a.gid)));
+    }
+
+    fn save(&self) -> Result<(), String> {
+        match &self.path {
+            Some(path) => save_json(path, &self.patches, "patch archive"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether the characters of `needle` appear in `haystack` in order.
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// A stable hash of an article's contents (64 bit FNV-1a, as hex), so edits can be noticed
+/// without comparing whole bodies.
+pub fn contents_hash(contents: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in contents.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,104 @@
+use reqwest::StatusCode;
+use serde_json::Value;
+use serenity::async_trait;
+
+use crate::config::Config;
+use crate::news::{NewsError, DOTA_APP_ID};
+
+/// Somewhere the current public build of the game can be looked up.
+#[async_trait]
+pub trait BuildSource: Send + Sync {
+    /// The version the Steam client currently requires, which changes with every shipped build.
+    async fn fetch_build(&self) -> Result<u64, NewsError>;
+}
+
+/// The Steam Web API's `UpToDateCheck`, which reports the required client version whether or
+/// not any patch notes were posted.
+pub struct SteamBuilds {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl SteamBuilds {
+    pub fn new(config: &Config) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .expect("Error creating HTTP client");
+        Self {
+            client,
+            url: build_url(config),
+        }
+    }
+}
+
+pub fn build_url(config: &Config) -> String {
+    // Asking about version 0 always gets the required version back
+    format!(
+        "{}/ISteamApps/UpToDateCheck/v1/?appid={}&version=0&format=json",
+        config.steam_api_url, DOTA_APP_ID
+    )
+}
+
+#[async_trait]
+impl BuildSource for SteamBuilds {
+    async fn fetch_build(&self) -> Result<u64, NewsError> {
+        let response =
+            self.client.get(&self.url).send().await.map_err(|e| {
+                NewsError::Failed(format!("Error checking for new builds: {:?}", e))
+            })?;
+        match response.status() {
+            StatusCode::TOO_MANY_REQUESTS => return Err(NewsError::RateLimited(None)),
+            status if !status.is_success() => {
+                return Err(NewsError::Failed(format!(
+                    "Valve API responded with status {} to build check",
+                    status
+                )));
+            }
+            _ => (),
+        }
+
+        let body = response.text().await.map_err(|e| {
+            NewsError::Failed(format!(
+                "Failed to get body of response from reqwest: {:?}",
+                e
+            ))
+        })?;
+        parse_build(&body).map_err(NewsError::Failed)
+    }
+}
+
+/// Parses an `UpToDateCheck` response body into the required version.
+pub fn parse_build(body: &str) -> Result<u64, String> {
+    let json: Value = serde_json::from_str(body).map_err(|e| {
+        format!(
+            "Failed to convert build check response from Valve API with error: {:?}",
+            e
+        )
+    })?;
+
+    let response = json
+        .get("response")
+        .ok_or_else(|| String::from("No response found in build check from Valve API"))?;
+    if response.get("success") != Some(&Value::Bool(true)) {
+        return Err(format!(
+            "Build check from Valve API was unsuccessful... {}",
+            response
+        ));
+    }
+    match response.get("required_version") {
+        Some(Value::Number(version)) => version.as_u64().ok_or_else(|| {
+            format!(
+                "Build check from Valve API had required_version {} that isn't a u64",
+                version
+            )
+        }),
+        Some(v) => Err(format!(
+            "Build check from Valve API had property required_version being something other than a number... {}",
+            v
+        )),
+        None => Err(String::from(
+            "No required_version found in build check from Valve API",
+        )),
+    }
+}
@@ -1,16 +1,28 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Duration, Utc};
 use lazy_static::lazy_static;
 use rand::Rng;
-use serde_json::Value;
 use serenity::futures::lock::Mutex;
-use serenity::model::id::ChannelId;
-use serenity::{model::id::GuildId, CacheAndHttp};
-use std::{collections::HashMap, sync::Arc};
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::CacheAndHttp;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
-const SLEEP_TIME: u64 = 60;
+use crate::archive::SharedArchive;
+use crate::builds::{BuildSource, SteamBuilds};
+use crate::config::Config;
+use crate::diff::{line_changes, render_diff};
+use crate::discord::{Embed, MessageSink, OutgoingMessage, PresenceSource, SerenityDiscord};
+use crate::feed_routes::{AnnounceRole, FeedRule, VoiceNotice};
+use crate::mentions::{chunk_mentions, join_list, mention_list, MESSAGE_LIMIT};
+use crate::news::{Article, NewsError, NewsFetch, NewsSource, PatchSize, SteamNews};
+use crate::patch_changes::extract_changes;
+use crate::playing::{GameMatcher, GameStatus, Player};
+use crate::poll_schedule::PollSchedule;
+use crate::subscriptions::SharedSubscriptions;
+use crate::user_prefs::SharedPrefs;
+use crate::webhooks::{AnnouncementSink, WebhookSink};
 
 lazy_static! {
-    static ref NEWEST: Arc<Mutex<(DateTime<Utc>, String)>> = Arc::new(Mutex::new((Utc::now(), String::default()))); // (time, gid)
     static ref CHANNELS: Arc<Mutex<HashMap<GuildId, [u64; 2]>>> = {
         let hash : HashMap<GuildId, [u64; 2]> = [
             (GuildId::from(434511133383065620u64), [999205229067259934, 999205213783208016]),
@@ -20,345 +32,1000 @@ lazy_static! {
     };
 }
 
-pub async fn check_updates(cache_and_http: &Arc<CacheAndHttp>) {
+pub async fn check_updates(
+    cache_and_http: &Arc<CacheAndHttp>,
+    config: &Config,
+    archive: SharedArchive,
+    subscriptions: SharedSubscriptions,
+    prefs: SharedPrefs,
+) {
     while !crate::READY.load(std::sync::atomic::Ordering::Relaxed) {
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
 
+    let news = SteamNews::new(config);
+    let builds = SteamBuilds::new(config);
+    let discord = SerenityDiscord::new(cache_and_http.cache.clone(), cache_and_http.http.clone());
+    let channels = CHANNELS.lock().await.clone();
+    let mut checker = UpdateChecker::new(Utc::now(), channels)
+        .news_maxlength(config.news_maxlength)
+        .routes(config.routes.clone())
+        .announce_roles(config.announce_roles.clone())
+        .crosspost(config.crosspost_guilds.clone())
+        .archive(archive)
+        .subscriptions(subscriptions)
+        .prefs(prefs)
+        .game(config.game.clone())
+        .voice_notices(config.voice_notices.clone());
+    let webhook_client = reqwest::Client::builder()
+        .timeout(config.request_timeout)
+        .build()
+        .expect("Error creating HTTP client");
+    for webhook in &config.webhooks {
+        checker = checker.sink(
+            webhook.guild_id,
+            WebhookSink::new(webhook_client.clone(), webhook.clone()),
+        );
+    }
+    let mut schedule = PollSchedule::new(config);
+
     loop {
-        let initial_request = match reqwest::get("http://api.steampowered.com/ISteamNews/GetNewsForApp/v0002/?appid=570&count=10&maxlength=300&format=json").await {
-                Ok(response) => response,
-                Err(e) => {
-                    eprintln!("Error checking for updates: {:?}", e);
-                    tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
-                    continue;
-                }
-            };
-        let response_body = match initial_request.text().await {
-            Ok(response) => response,
-            Err(e) => {
-                eprintln!("Failed to get body of response from reqwest: {:?}", e);
-                tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
-                continue;
+        let results = [
+            checker.poll(&news, &discord).await.map(drop),
+            checker.poll_build(&builds, &discord).await.map(drop),
+        ];
+        checker.ping_deferred(Utc::now(), &discord).await;
+        let mut failure = None;
+        for result in results {
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                failure = Some(e);
             }
+        }
+        match failure {
+            None => schedule.record_success(),
+            Some(NewsError::RateLimited(retry_after)) => schedule.record_failure(retry_after),
+            Some(NewsError::Failed(_)) => schedule.record_failure(None),
+        }
+        tokio::time::sleep(schedule.next_delay(Utc::now())).await;
+    }
+}
+
+/// More new items than this for one channel in one poll are announced as a single digest.
+const DIGEST_THRESHOLD: usize = 3;
+
+/// Longest diff shown when announced notes are edited, leaving room for the rest of the embed.
+const EDIT_DIFF_LENGTH: usize = 3500;
+
+/// Patch notes and a build change this close together are treated as the same update.
+const SAME_UPDATE_WINDOW_MINS: i64 = 120;
+
+/// Longest a restart ping waits for a player's match to end.
+pub const DEFERRED_PING_TIMEOUT_MINS: i64 = 90;
+
+/// A restart ping held back until a player can act on it.
+#[derive(Clone, Copy, Debug)]
+struct DeferredPing {
+    user_id: UserId,
+    /// When the ping would have been sent.
+    since: DateTime<Utc>,
+    /// When the player's quiet hours end, if that's what it waits for rather than a match.
+    until: Option<DateTime<Utc>>,
+}
+
+/// A change in the game's public build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildChange {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Watches the news feed for patch notes and the game's build for silent updates, announcing
+/// each through every guild's routes and pinging whoever is playing in the guild's ping
+/// channel.
+pub struct UpdateChecker {
+    since: DateTime<Utc>,
+    /// Contents of every article that has been announced, by gid, to notice edits by.
+    announced: HashMap<String, String>,
+    /// The `news_maxlength` articles' contents are fetched with.
+    news_maxlength: u32,
+    /// Messages each article was announced in, by gid, so edits can reply to them.
+    posted: HashMap<String, Vec<(ChannelId, MessageId)>>,
+    channels: HashMap<GuildId, [u64; 2]>, // (gid, [ping, updates])
+    routes: HashMap<GuildId, Vec<FeedRule>>,
+    announce_roles: HashMap<GuildId, AnnounceRole>,
+    /// Guilds whose announcement channels should publish updates to their followers.
+    crosspost: HashSet<GuildId>,
+    /// Where announced articles are recorded, if anywhere.
+    archive: Option<SharedArchive>,
+    /// Who to mention when patch notes change the heroes they follow.
+    subscriptions: Option<SharedSubscriptions>,
+    /// Webhooks for one guild's announcements, or for all of them if the guild is `None`.
+    sinks: Vec<(Option<GuildId>, Box<dyn AnnouncementSink>)>,
+    /// How players running the game are recognized.
+    game: GameMatcher,
+    /// Guilds that tell players in voice channels to restart there too.
+    voice_notices: HashMap<GuildId, VoiceNotice>,
+    build: Option<u64>,
+    /// When patch notes were last announced, until a build change is matched to them.
+    last_notes: Option<DateTime<Utc>>,
+    /// When a build change was last announced, until patch notes are matched to it.
+    last_build_change: Option<DateTime<Utc>>,
+    /// Players whose restart ping waits for their match or quiet hours to end.
+    deferred: HashMap<GuildId, Vec<DeferredPing>>,
+    /// Users' quiet hours, if anyone's set them.
+    prefs: Option<SharedPrefs>,
+}
+
+impl UpdateChecker {
+    /// Articles published before `since` are never announced.
+    pub fn new(since: DateTime<Utc>, channels: HashMap<GuildId, [u64; 2]>) -> Self {
+        Self {
+            since,
+            announced: HashMap::new(),
+            news_maxlength: 0,
+            posted: HashMap::new(),
+            channels,
+            routes: HashMap::new(),
+            announce_roles: HashMap::new(),
+            crosspost: HashSet::new(),
+            archive: None,
+            subscriptions: None,
+            sinks: vec![],
+            game: GameMatcher::default(),
+            voice_notices: HashMap::new(),
+            build: None,
+            last_notes: None,
+            last_build_change: None,
+            deferred: HashMap::new(),
+            prefs: None,
+        }
+    }
+
+    /// Sets which news items each guild wants and where. Guilds without routes get patch
+    /// notes in their updates channel.
+    pub fn routes(mut self, routes: HashMap<GuildId, Vec<FeedRule>>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Sets the role each guild wants mentioned with patch announcements.
+    pub fn announce_roles(mut self, roles: HashMap<GuildId, AnnounceRole>) -> Self {
+        self.announce_roles = roles;
+        self
+    }
+
+    /// Sets the guilds whose updates are crossposted when their updates channel is an
+    /// announcement channel.
+    pub fn crosspost(mut self, guilds: HashSet<GuildId>) -> Self {
+        self.crosspost = guilds;
+        self
+    }
+
+    /// Sets the `news_maxlength` articles are fetched with, so archived contents cut to
+    /// another length aren't mistaken for edits.
+    pub fn news_maxlength(mut self, maxlength: u32) -> Self {
+        self.news_maxlength = maxlength;
+        self
+    }
+
+    /// Records every announced article in `archive`.
+    pub fn archive(mut self, archive: SharedArchive) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Mentions users subscribed in `subscriptions` when patch notes change their heroes.
+    pub fn subscriptions(mut self, subscriptions: SharedSubscriptions) -> Self {
+        self.subscriptions = Some(subscriptions);
+        self
+    }
+
+    /// Sets how players running the game are recognized.
+    pub fn game(mut self, game: GameMatcher) -> Self {
+        self.game = game;
+        self
+    }
+
+    /// Holds pings for users in the quiet hours set in `prefs`.
+    pub fn prefs(mut self, prefs: SharedPrefs) -> Self {
+        self.prefs = Some(prefs);
+        self
+    }
+
+    /// Sets the guilds that post restart notices in their players' voice channels.
+    pub fn voice_notices(mut self, notices: HashMap<GuildId, VoiceNotice>) -> Self {
+        self.voice_notices = notices;
+        self
+    }
+
+    /// Adds a webhook that gets `guild_id`'s announcements, or everything if `None`.
+    pub fn sink(
+        mut self,
+        guild_id: Option<GuildId>,
+        sink: impl AnnouncementSink + 'static,
+    ) -> Self {
+        self.sinks.push((guild_id, Box::new(sink)));
+        self
+    }
+
+    /// The message announcing `embed` in a guild, mentioning its role if it wants to hear
+    /// about a patch of `size`.
+    fn update_message(
+        &self,
+        guild_id: GuildId,
+        embed: Embed,
+        size: Option<PatchSize>,
+    ) -> OutgoingMessage {
+        let mention = match (self.announce_roles.get(&guild_id), size) {
+            (Some(role), Some(size)) if role.wants(size) => Some(format!("<@&{}>", role.role_id)),
+            _ => None,
         };
-        let json: Value = match serde_json::from_str(&response_body) {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!(
-                    "Failed to convert response from Valve API with error: {:?}",
-                    e
-                );
-                tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
-                continue;
-            }
+        OutgoingMessage {
+            content: mention,
+            ..OutgoingMessage::embed(embed)
+        }
+    }
+
+    fn rules(&self, guild_id: GuildId) -> Vec<FeedRule> {
+        match (self.routes.get(&guild_id), self.channels.get(&guild_id)) {
+            (Some(rules), _) => rules.clone(),
+            (None, Some(channels)) => vec![FeedRule::patch_notes(ChannelId(channels[1]))],
+            (None, None) => vec![],
+        }
+    }
+
+    /// The channels of a guild's routes that want restart pings, which is where build changes
+    /// are announced.
+    fn restart_channels(&self, guild_id: GuildId) -> Vec<ChannelId> {
+        let mut channels: Vec<ChannelId> = self
+            .rules(guild_id)
+            .into_iter()
+            .filter(|rule| rule.restart_ping)
+            .map(|rule| rule.channel_id)
+            .collect();
+        channels.sort();
+        channels.dedup();
+        channels
+    }
+
+    /// Where a guild's players are told to restart: its ping channel, or the first route that
+    /// wants restart pings if it only has routes.
+    fn ping_channel(&self, guild_id: GuildId) -> Option<ChannelId> {
+        match self.channels.get(&guild_id) {
+            Some(channels) => Some(ChannelId(channels[0])),
+            None => self
+                .rules(guild_id)
+                .into_iter()
+                .find(|rule| rule.restart_ping)
+                .map(|rule| rule.channel_id),
+        }
+    }
+
+    /// Checks the game's build once and announces it if it changed. The first build seen is
+    /// only remembered, and the first change shortly after patch notes were announced is
+    /// assumed to be the same update.
+    pub async fn poll_build<B, D>(
+        &mut self,
+        builds: &B,
+        discord: &D,
+    ) -> Result<Option<BuildChange>, NewsError>
+    where
+        B: BuildSource + ?Sized,
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        let build = builds.fetch_build().await?;
+        let change = match self.build.replace(build) {
+            Some(from) if from != build => BuildChange { from, to: build },
+            _ => return Ok(None),
         };
+        println!("New build found: {} -> {}", change.from, change.to);
+        if take_recent(&mut self.last_notes) {
+            return Ok(None);
+        }
 
-        let appnews = match json.get("appnews") {
-            Some(appnews) => appnews,
-            None => {
-                eprintln!("No appnews found in response from Valve API");
-                tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
-                continue;
-            }
+        let embed = Embed {
+            title: format!(
+                "Dota client updated (build {} → {})",
+                change.from, change.to
+            ),
+            author: None,
+            description: String::from(
+                "Valve shipped a new client build. No patch notes have been published for it yet.",
+            ),
+            timestamp: Some(Utc::now()),
+            color: rand::thread_rng().gen_range(0x000000..=0xffffff),
         };
+        // Without notes there's no telling how big the update is
+        let guilds = self
+            .post_update(&embed, Some(PatchSize::Minor), discord)
+            .await;
+        self.ping_players(&guilds, discord).await;
+        self.last_build_change = Some(Utc::now());
+        Ok(Some(change))
+    }
 
-        let newsitems = match appnews.get("newsitems") {
-            Some(Value::Array(newsitems)) => newsitems,
-            Some(v) => {
-                eprintln!("Response from Valve API had property newsitems being something other than an array... {}", v);
-                tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
-                continue;
-            }
-            None => {
-                eprintln!("No newsitems found in response from Valve API");
-                tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
-                continue;
-            }
+    /// Fetches the news once and announces any new items some guild wants, returning what was
+    /// announced oldest first.
+    pub async fn poll<N, D>(&mut self, news: &N, discord: &D) -> Result<Vec<Article>, NewsError>
+    where
+        N: NewsSource + ?Sized,
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        let articles = match news.fetch_news().await? {
+            NewsFetch::Updated(articles) => articles,
+            NewsFetch::NotModified => return Ok(vec![]),
         };
 
-        struct Article {
-            pub title: String,
-            pub author: String,
-            pub url: String,
-            pub date: DateTime<Utc>,
+        let edits = self.find_edits(&articles).await;
+        let updates = self.find_updates(articles);
+        if !updates.is_empty() {
+            self.announce(&updates, discord).await;
+        }
+        for (article, old_contents) in &edits {
+            self.announce_edit(article, old_contents, discord).await;
         }
 
-        let mut updated: Option<Article> = None;
+        let changed: Vec<Article> = updates
+            .iter()
+            .cloned()
+            .chain(edits.into_iter().map(|(article, _)| article))
+            .collect();
+        if let (Some(archive), false) = (&self.archive, changed.is_empty()) {
+            if let Err(e) = archive.lock().await.record(&changed, self.news_maxlength) {
+                eprintln!("{}", e);
+            }
+        }
+        Ok(updates)
+    }
 
-        for item in newsitems {
-            let tags_raw = match item.get("tags") {
-                Some(Value::Array(tags)) => tags,
-                Some(v) => {
-                    eprintln!("Response from Valve API had property tags being something other than an array... {}", v);
+    /// Picks out announced articles whose contents have changed, with what they used to say.
+    /// Articles announced before a restart are compared against the archive, unless it holds
+    /// them cut to a different length. Those, and contents that were only cut short before, are
+    /// updated in the archive without being announced.
+    async fn find_edits(&mut self, articles: &[Article]) -> Vec<(Article, String)> {
+        let mut rebaselined = vec![];
+        if let Some(archive) = &self.archive {
+            let archive = archive.lock().await;
+            for article in articles {
+                if self.announced.contains_key(&article.gid) {
                     continue;
                 }
-                None => {
-                    continue;
+                match archive.get(&article.gid) {
+                    Some(patch) if patch.maxlength == Some(self.news_maxlength) => {
+                        self.announced
+                            .insert(patch.gid.clone(), patch.contents.clone());
+                    }
+                    Some(_) => {
+                        self.announced
+                            .insert(article.gid.clone(), article.contents.clone());
+                        rebaselined.push(article.clone());
+                    }
+                    None => (),
                 }
-            };
-            let found_patchnotes = tags_raw.iter().find_map(|tag| match tag {
-                Value::String(inner_tag) if inner_tag == "patchnotes" => Some(()),
-                _ => None,
-            });
-            if found_patchnotes.is_none() {
-                continue;
             }
+        }
 
-            let item_date = match item.get("date") {
-                Some(Value::Number(date)) => match date.as_i64() {
-                    Some(date) => Utc.timestamp(date, 0),
-                    None => {
-                        eprintln!(
-                            "Failed to convert date for newsitem entry from Valve API to i64"
-                        );
-                        continue;
-                    }
-                },
-                Some(v) => {
-                    eprintln!("newsitem entry from Valve API had property date being something other than a number... {}", v);
+        let mut edits = vec![];
+        for article in articles {
+            if let Some(contents) = self.announced.get_mut(&article.gid) {
+                if *contents == article.contents {
                     continue;
                 }
-                None => {
-                    eprintln!("No date found for newsitem entry in response from Valve API");
-                    continue;
+                let old_contents = std::mem::replace(contents, article.contents.clone());
+                if is_truncation(&old_contents, &article.contents) {
+                    rebaselined.push(article.clone());
+                } else {
+                    println!("Edited update found: {}", article.title);
+                    edits.push((article.clone(), old_contents));
                 }
-            };
-
-            macro_rules! get_string {
-                ($item: expr, $property: expr) => {
-                    match $item.get($property) {
-                        Some(Value::String(string)) => string.to_string(),
-                        Some(v) => {
-                            eprintln!(
-                                "newsitem entry from Valve API had property {} being something other than a string... {}",
-                                $property, v
-                            );
-                            continue;
-                        }
-                        None => {
-                            eprintln!("No {} found for newsitem entry in response from Valve API", $property);
-                            continue;
-                        }
-                    }
-                };
             }
+        }
 
-            let item_gid = get_string!(item, "gid");
-            let item_title = get_string!(item, "title");
-            let item_author = get_string!(item, "author");
-            let item_url = get_string!(item, "url");
-
-            let mut newest = NEWEST.lock().await;
-            if item_date >= newest.0 && item_gid != newest.1 {
-                println!("New update found: {}", item_title);
-                newest.0 = item_date;
-                newest.1 = item_gid;
-                updated = Some(Article {
-                    title: item_title,
-                    author: item_author,
-                    url: item_url,
-                    date: item_date,
-                });
+        if let (Some(archive), false) = (&self.archive, rebaselined.is_empty()) {
+            if let Err(e) = archive
+                .lock()
+                .await
+                .record(&rebaselined, self.news_maxlength)
+            {
+                eprintln!("{}", e);
             }
         }
+        edits
+    }
 
-        if let Some(article) = updated {
-            let cache = &cache_and_http.cache;
-            let http = &cache_and_http.http;
+    /// Picks out the items some guild's routes match that haven't been announced yet, oldest
+    /// first, remembering them so they aren't announced again.
+    pub fn find_updates(&mut self, articles: Vec<Article>) -> Vec<Article> {
+        let guilds: HashSet<GuildId> = self
+            .routes
+            .keys()
+            .chain(self.channels.keys())
+            .copied()
+            .collect();
+        let rules: Vec<FeedRule> = guilds.into_iter().flat_map(|g| self.rules(g)).collect();
+        let mut updates: Vec<Article> = articles
+            .into_iter()
+            .filter(|article| {
+                rules.iter().any(|rule| rule.matches(article))
+                    && article.date >= self.since
+                    && !self.announced.contains_key(&article.gid)
+            })
+            .collect();
+        updates.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.gid.cmp(&b.gid)));
+        updates.dedup_by(|a, b| a.gid == b.gid);
+        for article in &updates {
+            println!("New update found: {}", article.title);
+            self.announced
+                .insert(article.gid.clone(), article.contents.clone());
+        }
+        updates
+    }
 
-            let guilds = cache.guilds();
+    async fn announce<D>(&mut self, articles: &[Article], discord: &D)
+    where
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        let mut ping_guilds = vec![];
+        for guild in discord.guilds() {
+            let rules = self.rules(guild);
+            if rules.is_empty() {
+                eprintln!("No channels found for guild {}", guild);
+                continue;
+            }
 
-            for guild in guilds {
-                let channels_raw: [u64; 2] = {
-                    let channels_lock = CHANNELS.lock().await;
-                    match channels_lock.get(&guild) {
-                        Some(channels) => channels.to_owned(),
-                        None => {
-                            eprintln!("No channels found for guild {}", guild);
-                            continue;
-                        }
-                    }
-                };
-                let channel_map = match cache.guild_channels(guild) {
-                    Some(channel_map) => channel_map,
-                    None => {
-                        eprintln!("Failed to get references to channels for guild {}", guild);
-                        continue;
-                    }
-                };
-                let updates_channel = match channel_map.get(&ChannelId::from(channels_raw[1])) {
-                    Some(channel) => channel,
+            // Each channel gets every item any of its rules match, once, in order
+            let mut routed: Vec<(ChannelId, Vec<&Article>)> = vec![];
+            let mut ping = false;
+            for rule in &rules {
+                let matched: Vec<&Article> = articles.iter().filter(|a| rule.matches(a)).collect();
+                if matched.is_empty() {
+                    continue;
+                }
+                ping |= rule.restart_ping;
+                let index = match routed.iter().position(|(id, _)| *id == rule.channel_id) {
+                    Some(index) => index,
                     None => {
-                        eprintln!(
-                            "No updates channel found for guild {} (should be under ID {})",
-                            guild, channels_raw[1]
-                        );
-                        continue;
-                    }
-                };
-
-                match updates_channel
-                    .send_message(http, |m| {
-                        m.embed(|e| {
-                            e.title(&article.title);
-                            e.author(|a| a.name(&article.author));
-                            e.description(format!("A new update is available! Please see [here]({}) for more information.", article.url));
-                            e.timestamp(article.date.to_rfc3339());
-                            e.color(rand::thread_rng().gen_range(0x000000..=0xffffff));
-                            e
-                        })
-                    })
-                    .await
-                {
-                    Ok(_) => (),
-                    Err(e) => {
-                        eprintln!("Failed to send message to updates channel for guild {} with error {}", guild, e);
+                        routed.push((rule.channel_id, vec![]));
+                        routed.len() - 1
                     }
                 };
+                routed[index].1.extend(matched);
+            }
+            if ping {
+                ping_guilds.push(guild);
             }
 
-            let players = get_users_playing(cache_and_http).await;
-            for (guild_id, guild_players) in players.into_iter() {
-                let channels_raw: [u64; 2] = {
-                    let channels_lock = CHANNELS.lock().await;
-                    match channels_lock.get(&guild_id) {
-                        Some(channels) => channels.to_owned(),
-                        None => {
-                            eprintln!("No channels found for guild {}", guild_id);
-                            continue;
+            let mut guild_articles = vec![];
+            for (channel_id, matched) in &routed {
+                let channel_id = *channel_id;
+                guild_articles.extend(matched.iter().copied());
+                for announcement in announcement_embeds(matched.clone()) {
+                    let message = self.update_message(guild, announcement.embed, announcement.size);
+                    let message_id = self.send_update(guild, channel_id, &message, discord).await;
+                    if let Some(message_id) = message_id {
+                        for gid in announcement.gids {
+                            self.posted
+                                .entry(gid)
+                                .or_default()
+                                .push((channel_id, message_id));
                         }
                     }
-                };
-                let channel_map = match cache.guild_channels(guild_id) {
-                    Some(channel_map) => channel_map,
-                    None => {
-                        eprintln!(
-                            "Failed to get references to channels for guild {}",
-                            guild_id
-                        );
-                        continue;
-                    }
-                };
+                }
+            }
+            self.notify_subscribers(guild, &routed, discord).await;
+            let embeds: Vec<Embed> = announcement_embeds(guild_articles)
+                .into_iter()
+                .map(|announcement| announcement.embed)
+                .collect();
+            self.notify_sinks(Some(guild), &embeds).await;
+        }
+        let embeds: Vec<Embed> = announcement_embeds(articles.iter().collect())
+            .into_iter()
+            .map(|announcement| announcement.embed)
+            .collect();
+        self.notify_sinks(None, &embeds).await;
 
-                let ping_channel = match channel_map.get(&ChannelId::from(channels_raw[0])) {
-                    Some(channel) => channel,
-                    None => {
-                        eprintln!(
-                            "No ping channel found for guild {} (should be under ID {})",
-                            guild_id, channels_raw[0]
-                        );
-                        continue;
-                    }
+        // Notes for a build that was already pinged about don't need a second ping
+        let notes = articles.iter().any(|article| article.has_tag("patchnotes"));
+        let pinged = notes && take_recent(&mut self.last_build_change);
+        if !ping_guilds.is_empty() && !pinged {
+            self.ping_players(&ping_guilds, discord).await;
+        }
+        if notes {
+            self.last_notes = Some(Utc::now());
+        }
+    }
+
+    /// Posts the lines Valve changed in an announced article, replying to the original
+    /// announcement where it's known.
+    async fn announce_edit<D>(&self, article: &Article, old_contents: &str, discord: &D)
+    where
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        let changes = line_changes(old_contents, &article.contents);
+        if changes.is_empty() {
+            return;
+        }
+        let embed = Embed {
+            title: format!("Updated patch notes: {}", article.title),
+            author: Some(article.author.clone()),
+            description: format!(
+                "Valve edited [these notes]({}).\n{}",
+                article.url,
+                render_diff(&changes, EDIT_DIFF_LENGTH)
+            ),
+            timestamp: Some(Utc::now()),
+            color: rand::thread_rng().gen_range(0x000000..=0xffffff),
+        };
+
+        for guild in discord.guilds() {
+            let mut channels: Vec<ChannelId> = self
+                .rules(guild)
+                .into_iter()
+                .filter(|rule| rule.matches(article))
+                .map(|rule| rule.channel_id)
+                .collect();
+            channels.sort();
+            channels.dedup();
+            if channels.is_empty() {
+                continue;
+            }
+            for channel_id in channels {
+                let reply_to = self
+                    .posted
+                    .get(&article.gid)
+                    .and_then(|posts| posts.iter().find(|(posted_in, _)| *posted_in == channel_id))
+                    .copied();
+                let message = OutgoingMessage {
+                    reply_to,
+                    ..OutgoingMessage::embed(embed.clone())
                 };
+                self.send_update(guild, channel_id, &message, discord).await;
+            }
+            self.notify_sinks(Some(guild), std::slice::from_ref(&embed))
+                .await;
+        }
+        self.notify_sinks(None, &[embed]).await;
+    }
 
-                match guild_players.len() {
-                    0 => (),
-                    1 => {
-                        match ping_channel
-                            .say(
-                                http,
-                                format!(
-                                    "Attention <@{}> : You need to restart dota. There is an update!",
-                                    guild_players[0].id
-                                ),
-                            )
-                            .await
-                        {
-                            Ok(_) => (),
-                            Err(e) => {
-                                eprintln!("Failed to send message to ping channel: {}", e);
-                            }
-                        };
-                    }
-                    2 => {
-                        match ping_channel
-                            .say(
-                                http,
-                                format!(
-                                    "Attention <@{}> and <@{}> : You need to restart dota. There is an update!",
-                                    guild_players[0].id,
-                                    guild_players[1].id
-                                ),
-                            )
-                            .await
-                        {
-                            Ok(_) => (),
-                            Err(e) => {
-                                eprintln!("Failed to send message to ping channel: {}", e);
-                            }
-                        };
-                    }
-                    _ => {
-                        let mut players_string = String::from("Attention ");
-                        for player in &guild_players[..guild_players.len() - 1] {
-                            players_string.push_str(&format!("<@{}> ,", player.id));
-                        }
-                        players_string.push_str(&format!(
-                            "and <@{}> : You need to restart dota. There is an update!",
-                            guild_players[guild_players.len() - 1].id
-                        ));
-                        match ping_channel.say(http, players_string).await {
-                            Ok(_) => (),
-                            Err(e) => {
-                                eprintln!("Failed to send message to ping channel: {}", e);
-                            }
-                        };
+    /// Mentions everyone in `guild_id` following a hero that the routed patch notes change, in
+    /// the first channel each article was announced in.
+    async fn notify_subscribers<D>(
+        &self,
+        guild_id: GuildId,
+        routed: &[(ChannelId, Vec<&Article>)],
+        discord: &D,
+    ) where
+        D: MessageSink + ?Sized,
+    {
+        let subscriptions = match &self.subscriptions {
+            Some(subscriptions) => subscriptions,
+            None => return,
+        };
+        let mut notes: Vec<(ChannelId, &Article)> = vec![];
+        for (channel_id, articles) in routed {
+            for article in articles.iter().filter(|a| a.has_tag("patchnotes")) {
+                if !notes.iter().any(|(_, noted)| noted.gid == article.gid) {
+                    notes.push((*channel_id, article));
+                }
+            }
+        }
+        for (channel_id, article) in notes {
+            let heroes = extract_changes(&article.contents).hero_names();
+            if heroes.is_empty() {
+                continue;
+            }
+            let subscribers = subscriptions.lock().await.subscribers(guild_id, &heroes);
+            let mut messages = subscriber_ping(article, &subscribers);
+            // Subscribers in their quiet hours are still listed, just not notified
+            let now = Utc::now();
+            for message in &mut messages {
+                let mut allowed = vec![];
+                for user_id in message.allowed_users.take().unwrap_or_default() {
+                    if self.quiet_until(user_id, now).await.is_none() {
+                        allowed.push(user_id);
                     }
                 }
+                message.allowed_users = Some(allowed);
             }
+            send_pings(channel_id, &messages, discord).await;
         }
+    }
 
-        tokio::time::sleep(std::time::Duration::from_secs(SLEEP_TIME)).await;
+    /// Sends `embeds` to the webhooks for `guild_id`, or to the global ones if `None`.
+    async fn notify_sinks(&self, guild_id: Option<GuildId>, embeds: &[Embed]) {
+        for (sink_guild, sink) in &self.sinks {
+            if *sink_guild != guild_id {
+                continue;
+            }
+            for embed in embeds {
+                if let Err(e) = sink.announce(embed).await {
+                    eprintln!("Failed to send announcement to webhook: {}", e);
+                }
+            }
+        }
     }
-}
 
-pub async fn get_users_playing(
-    cache_and_http: &Arc<CacheAndHttp>,
-) -> HashMap<GuildId, Vec<serenity::model::user::User>> {
-    let cache = &cache_and_http.cache;
-    let guilds = cache.guilds();
-    let returnable: HashMap<GuildId, Vec<serenity::model::user::User>> = guilds
-        .iter()
-        .filter_map(|guild_id| {
-            let guild = match cache.guild(guild_id) {
-                Some(g) => g,
-                None => {
+    /// Sends `embed` to the routes of every guild that wants restart pings, returning those
+    /// guilds.
+    async fn post_update<D>(
+        &self,
+        embed: &Embed,
+        size: Option<PatchSize>,
+        discord: &D,
+    ) -> Vec<GuildId>
+    where
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        let mut guilds = vec![];
+        for guild in discord.guilds() {
+            let channels = self.restart_channels(guild);
+            if channels.is_empty() {
+                continue;
+            }
+            guilds.push(guild);
+            let message = self.update_message(guild, embed.clone(), size);
+            for channel_id in channels {
+                self.send_update(guild, channel_id, &message, discord).await;
+            }
+            self.notify_sinks(Some(guild), std::slice::from_ref(embed))
+                .await;
+        }
+        self.notify_sinks(None, std::slice::from_ref(embed)).await;
+        guilds
+    }
+
+    /// Sends an update to one of a guild's channels, publishing it to followers if the guild
+    /// wants that and the channel is an announcement channel.
+    async fn send_update<D>(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        message: &OutgoingMessage,
+        discord: &D,
+    ) -> Option<MessageId>
+    where
+        D: MessageSink + ?Sized,
+    {
+        let message_id = match discord.send_message(channel_id, message).await {
+            Ok(message_id) => message_id,
+            Err(e) => {
+                eprintln!(
+                    "Failed to send message to updates channel for guild {} with error {}",
+                    guild_id, e
+                );
+                return None;
+            }
+        };
+        if !self.crosspost.contains(&guild_id) {
+            return Some(message_id);
+        }
+
+        match discord.is_announcement_channel(channel_id).await {
+            Ok(true) => {
+                if let Err(e) = discord.crosspost(channel_id, message_id).await {
                     eprintln!(
-                        "Failed to get guild with id {} when checking presences.",
-                        guild_id.0
+                        "Failed to crosspost update in channel {} with error {}",
+                        channel_id, e
                     );
-                    return None;
                 }
-            };
+            }
+            Ok(false) => (),
+            Err(e) => eprintln!(
+                "Failed to get type of channel {} with error {}",
+                channel_id, e
+            ),
+        }
+        Some(message_id)
+    }
 
-            let presences = guild.presences;
-            let users_playing: Vec<serenity::model::user::User> = presences
+    /// Tells everyone playing in `guilds` to restart, in their guild's ping channel. Players
+    /// in a match can't restart yet and players in their quiet hours don't want to hear it, so
+    /// their pings wait for [`UpdateChecker::ping_deferred`].
+    async fn ping_players<D>(&mut self, guilds: &[GuildId], discord: &D)
+    where
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        let now = Utc::now();
+        discord.resolve_members(guilds).await;
+        let players = get_users_playing(discord, &self.game);
+        for (guild_id, guild_players) in players.into_iter() {
+            if !guilds.contains(&guild_id) {
+                continue;
+            }
+            let mut user_ids = vec![];
+            for player in guild_players {
+                let until = self.quiet_until(player.user_id, now).await;
+                if player.status == GameStatus::InMatch || until.is_some() {
+                    self.defer_ping(guild_id, player.user_id, now, until);
+                } else {
+                    user_ids.push(player.user_id);
+                }
+            }
+            self.send_restart_pings(guild_id, &user_ids, discord).await;
+        }
+    }
+
+    fn defer_ping(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        since: DateTime<Utc>,
+        until: Option<DateTime<Utc>>,
+    ) {
+        let deferred = self.deferred.entry(guild_id).or_default();
+        if !deferred.iter().any(|ping| ping.user_id == user_id) {
+            deferred.push(DeferredPing {
+                user_id,
+                since,
+                until,
+            });
+        }
+    }
+
+    /// When `user_id`'s quiet hours end, if they're in them at `now`.
+    async fn quiet_until(&self, user_id: UserId, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &self.prefs {
+            Some(prefs) => prefs.lock().await.get(user_id).quiet_until(now),
+            None => None,
+        }
+    }
+
+    /// Pings players whose restart ping was held back, once their match has ended (or they've
+    /// waited longer than [`DEFERRED_PING_TIMEOUT_MINS`]) or their quiet hours are over.
+    pub async fn ping_deferred<D>(&mut self, now: DateTime<Utc>, discord: &D)
+    where
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        if self.deferred.is_empty() {
+            return;
+        }
+        let players = get_users_playing(discord, &self.game);
+        let timeout = Duration::minutes(DEFERRED_PING_TIMEOUT_MINS);
+        let mut ready: Vec<(GuildId, DeferredPing)> = vec![];
+        for (guild_id, deferred) in self.deferred.iter_mut() {
+            let in_match: Vec<UserId> = players
+                .get(guild_id)
                 .into_iter()
-                .filter_map(|(user_id, presence)| {
-                    let user = match cache.user(user_id) {
-                        Some(u) => u,
-                        None => {
-                            eprintln!(
-                                "Failed to get user with id {} when checking presence.",
-                                user_id
-                            );
-                            return None;
-                        }
-                    };
-                    if !user.bot {
-                        let activities = presence.activities;
-                        for activity in activities {
-                            if activity.name.to_ascii_lowercase().contains("dota") {
-                                return Some(user);
-                            }
-                        }
-                    }
-                    None
+                .flatten()
+                .filter(|player| player.status == GameStatus::InMatch)
+                .map(|player| player.user_id)
+                .collect();
+            deferred.retain(|ping| {
+                let waiting = match ping.until {
+                    Some(until) => now < until,
+                    None => in_match.contains(&ping.user_id) && now - ping.since < timeout,
+                };
+                if !waiting {
+                    ready.push((*guild_id, *ping));
+                }
+                waiting
+            });
+        }
+        self.deferred.retain(|_, deferred| !deferred.is_empty());
+
+        let mut due: Vec<(GuildId, Vec<UserId>)> = vec![];
+        for (guild_id, ping) in ready {
+            // A match can end in the middle of someone's quiet hours
+            if let Some(until) = self.quiet_until(ping.user_id, now).await {
+                self.defer_ping(guild_id, ping.user_id, ping.since, Some(until));
+                continue;
+            }
+            match due.iter_mut().find(|(id, _)| *id == guild_id) {
+                Some((_, user_ids)) => user_ids.push(ping.user_id),
+                None => due.push((guild_id, vec![ping.user_id])),
+            }
+        }
+        for (guild_id, user_ids) in due {
+            self.send_restart_pings(guild_id, &user_ids, discord).await;
+        }
+    }
+
+    /// Tells `user_ids` to restart in the guild's ping channel and, if the guild wants voice
+    /// notices, in the text chat of the voice channels they're in.
+    async fn send_restart_pings<D>(&self, guild_id: GuildId, user_ids: &[UserId], discord: &D)
+    where
+        D: MessageSink + PresenceSource + ?Sized,
+    {
+        if user_ids.is_empty() {
+            return;
+        }
+        let mut ping_channel_users = user_ids.to_vec();
+        if let Some(notice) = self.voice_notices.get(&guild_id) {
+            let voice = discord.voice_channels(guild_id);
+            let mut in_voice: Vec<(ChannelId, Vec<UserId>)> = vec![];
+            for user_id in user_ids {
+                let channel_id = match voice.get(user_id) {
+                    Some(channel_id) => *channel_id,
+                    None => continue,
+                };
+                match in_voice.iter_mut().find(|(id, _)| *id == channel_id) {
+                    Some((_, users)) => users.push(*user_id),
+                    None => in_voice.push((channel_id, vec![*user_id])),
+                }
+            }
+            in_voice.sort_by_key(|(channel_id, _)| *channel_id);
+            for (channel_id, users) in &in_voice {
+                send_pings(*channel_id, &restart_ping(users), discord).await;
+            }
+            if notice.replace_ping {
+                ping_channel_users.retain(|user_id| !voice.contains_key(user_id));
+            }
+        }
+
+        let messages = restart_ping(&ping_channel_users);
+        if messages.is_empty() {
+            return;
+        }
+        match self.ping_channel(guild_id) {
+            Some(channel_id) => send_pings(channel_id, &messages, discord).await,
+            None => eprintln!("No ping channel found for guild {}", guild_id),
+        }
+    }
+}
+
+/// Whether `at` was within [`SAME_UPDATE_WINDOW_MINS`] of now, clearing it either way so it's
+/// only matched to one update.
+fn take_recent(at: &mut Option<DateTime<Utc>>) -> bool {
+    at.take()
+        .is_some_and(|at| Utc::now() - at < Duration::minutes(SAME_UPDATE_WINDOW_MINS))
+}
+
+/// Whether `old` is `new` cut short, as Steam does (with an ellipsis) to contents longer than
+/// `news_maxlength`.
+fn is_truncation(old: &str, new: &str) -> bool {
+    match old.strip_suffix("...").or_else(|| old.strip_suffix('…')) {
+        Some(cut) => new.starts_with(cut.trim_end()),
+        None => false,
+    }
+}
+
+/// Sends each of `messages` to a ping channel, stopping at the first failure since the rest
+/// would likely fail the same way.
+async fn send_pings<D>(channel_id: ChannelId, messages: &[OutgoingMessage], discord: &D)
+where
+    D: MessageSink + ?Sized,
+{
+    for message in messages {
+        if let Err(e) = discord.send_message(channel_id, message).await {
+            eprintln!("Failed to send message to ping channel: {}", e);
+            return;
+        }
+    }
+}
+
+/// An embed announcing one or more articles.
+struct Announcement {
+    embed: Embed,
+    size: Option<PatchSize>,
+    gids: Vec<String>,
+}
+
+impl Announcement {
+    fn new(embed: Embed, articles: &[&Article]) -> Self {
+        Self {
+            embed,
+            size: patch_size(articles),
+            gids: articles.iter().map(|article| article.gid.clone()).collect(),
+        }
+    }
+}
+
+/// The embeds announcing `articles`: one each, or a digest if there are many.
+fn announcement_embeds(mut articles: Vec<&Article>) -> Vec<Announcement> {
+    articles.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.gid.cmp(&b.gid)));
+    articles.dedup_by(|a, b| a.gid == b.gid);
+    if articles.len() > DIGEST_THRESHOLD {
+        vec![Announcement::new(digest_embed(&articles), &articles)]
+    } else {
+        articles
+            .into_iter()
+            .map(|article| Announcement::new(article_embed(article), &[article]))
+            .collect()
+    }
+}
+
+/// The largest patch among `articles`, or `None` if none of them are patch notes.
+fn patch_size(articles: &[&Article]) -> Option<PatchSize> {
+    let sizes = articles
+        .iter()
+        .filter(|article| article.has_tag("patchnotes"))
+        .map(|article| article.patch_size());
+    sizes.reduce(|a, b| match (a, b) {
+        (PatchSize::Minor, PatchSize::Minor) => PatchSize::Minor,
+        _ => PatchSize::Major,
+    })
+}
+
+fn article_embed(article: &Article) -> Embed {
+    Embed {
+        title: article.title.clone(),
+        author: Some(article.author.clone()),
+        description: format!(
+            "A new update is available! Please see [here]({}) for more information.",
+            article.url
+        ),
+        timestamp: Some(article.date),
+        color: rand::thread_rng().gen_range(0x000000..=0xffffff),
+    }
+}
+
+/// One embed linking to each of `articles`, which must be sorted oldest first.
+fn digest_embed(articles: &[&Article]) -> Embed {
+    let links: Vec<String> = articles
+        .iter()
+        .map(|article| format!("• [{}]({})", article.title, article.url))
+        .collect();
+    Embed {
+        title: format!("{} new updates", articles.len()),
+        author: None,
+        description: links.join("\n"),
+        timestamp: articles.last().map(|article| article.date),
+        color: rand::thread_rng().gen_range(0x000000..=0xffffff),
+    }
+}
+
+/// The messages telling `players` to restart their game, as many as it takes to mention
+/// them all, or none if nobody is playing.
+pub fn restart_ping(players: &[UserId]) -> Vec<OutgoingMessage> {
+    chunk_mentions(
+        players,
+        MESSAGE_LIMIT,
+        |player| *player,
+        |chunk| {
+            format!(
+                "Attention {}: You need to restart dota. There is an update!",
+                mention_list(chunk)
+            )
+        },
+    )
+}
+
+/// The messages telling `subscribers` which of their heroes `article` changes, none if
+/// nobody follows any of them.
+pub fn subscriber_ping(
+    article: &Article,
+    subscribers: &[(UserId, Vec<String>)],
+) -> Vec<OutgoingMessage> {
+    chunk_mentions(
+        subscribers,
+        MESSAGE_LIMIT,
+        |(user_id, _)| *user_id,
+        |chunk| {
+            let mut content = format!(
+                "[{}](<{}>) changes heroes you follow:",
+                article.title, article.url
+            );
+            for (user_id, heroes) in chunk {
+                content.push_str(&format!("\n<@{}>: {}", user_id, join_list(heroes)));
+            }
+            content
+        },
+    )
+}
+
+/// Everyone in each guild who's running the game, with what they're doing in it.
+pub fn get_users_playing<P: PresenceSource + ?Sized>(
+    presences: &P,
+    game: &GameMatcher,
+) -> HashMap<GuildId, Vec<Player>> {
+    presences
+        .guilds()
+        .into_iter()
+        .map(|guild_id| {
+            let players: Vec<Player> = presences
+                .presences(guild_id)
+                .into_iter()
+                .filter(|presence| !presence.bot)
+                .filter_map(|presence| {
+                    game.status(&presence.activities).map(|status| Player {
+                        user_id: presence.user_id,
+                        status,
+                    })
                 })
                 .collect();
-            Some((guild_id.clone(), users_playing))
+            (guild_id, players)
         })
-        .collect();
-    returnable
+        .collect()
 }
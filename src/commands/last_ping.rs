@@ -0,0 +1,265 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rand::Rng;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::time::Duration;
+
+use super::{followup_error, guild_id, option_value, CommandError, CommandResult, SlashCommand};
+use crate::discord::{MessageInfo, MessageSink, OutgoingMessage, SerenityDiscord};
+use crate::ping_history::SharedPingHistory;
+use crate::ping_scanner::{PingScanner, ScanDepth, ScanProgress, DEFAULT_DEPTH};
+use crate::user_prefs::{SharedPrefs, UserPrefs};
+
+/// How often the deferred response is edited with scan progress. Editing on every
+/// finished channel would burn through the webhook rate limit in large guilds.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(1500);
+/// Upper bound on messages searched per channel, used when only `since` is given so a quiet
+/// role in a busy guild can't page through years of history.
+const MAX_DEPTH: u64 = 5000;
+/// Furthest back `since` may reach, in days.
+const MAX_SINCE_DAYS: i64 = 365;
+
+/// `/lastping`: finds the most recent @Dote ping in the guild.
+pub struct LastPing {
+    pub prefs: SharedPrefs,
+    /// Pings the bot knows of without scanning, such as those sent by `/lfg`.
+    pub history: SharedPingHistory,
+    /// Most channels read at once.
+    pub scan_concurrency: usize,
+}
+
+#[async_trait]
+impl SlashCommand for LastPing {
+    fn name(&self) -> &'static str {
+        "lastping"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Displays the last time someone pinged for @Dote")
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("archived")
+                    .description("Also search threads archived in the last week")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("depth")
+                    .description("How many messages to search back in each channel (default 100)")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(MAX_DEPTH)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("since")
+                    .description("Only search messages from the last N days")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(MAX_SINCE_DAYS)
+                    .required(false)
+            })
+    }
+
+    // Scanning every channel can easily exceed Discord's 3 second response window
+    fn deferred(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let guild_id = guild_id(command)?;
+        let include_archived = match option_value(command, "archived") {
+            Some(CommandDataOptionValue::Boolean(archived)) => *archived,
+            _ => false,
+        };
+        let depth = scan_depth(command);
+
+        let color: i32 = rand::thread_rng().gen_range(0x000000..=0xffffff);
+        let mut errors = vec![];
+        let scanner = PingScanner::new(self.scan_concurrency).depth(depth);
+        let scanned = find_newest_ping(
+            ctx,
+            command,
+            &scanner,
+            guild_id,
+            include_archived,
+            color,
+            &mut errors,
+        )
+        .await;
+        let recorded = self.history.lock().await.last(guild_id).cloned();
+        let message = match (scanned, recorded) {
+            (Some(scanned), Some(recorded)) if recorded.id > scanned.id => recorded,
+            (Some(scanned), _) => scanned,
+            (None, Some(recorded)) => recorded,
+            (None, None) => {
+                let content = format!("No @Dote ping found {}", describe_depth(depth));
+                eprintln!("{}", content);
+                errors.push(content);
+                return Err(CommandError::Message(join_errors(&errors)));
+            }
+        };
+
+        let prefs = self.prefs.lock().await.get(command.user.id);
+        let content = describe_ping(&message, &prefs);
+        command
+            .edit_original_interaction_response(&ctx.http, |response| {
+                response.embed(|embed| embed.title("Last @Dote").description(content).color(color))
+            })
+            .await?;
+        let discord = SerenityDiscord::new(ctx.cache.clone(), ctx.http.clone());
+        let reply = OutgoingMessage {
+            reply_to: Some((message.channel_id, message.id)),
+            ..OutgoingMessage::text("Here")
+        };
+        if let Err(e) = discord.send_message(message.channel_id, &reply).await {
+            eprintln!("Error replying to message: {:?}", e);
+        }
+
+        if !errors.is_empty() {
+            followup_error(ctx, command, "Followup Errors", &join_errors(&errors)).await;
+        }
+        Ok(())
+    }
+}
+
+async fn find_newest_ping(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    scanner: &PingScanner,
+    guild_id: GuildId,
+    include_archived: bool,
+    color: i32,
+    errors: &mut Vec<String>,
+) -> Option<MessageInfo> {
+    let dote_role = match crate::GUILD_DOTE_ROLES.get(&guild_id) {
+        Some(role) => format!("<@&{}>", role),
+        None => {
+            let content = format!("Could not find dote role for guild {}", guild_id);
+            eprintln!("{}", content);
+            errors.push(content);
+            return None;
+        }
+    };
+
+    let discord = SerenityDiscord::new(ctx.cache.clone(), ctx.http.clone());
+    let channels = scanner
+        .scannable_channels(&discord, guild_id, include_archived, errors)
+        .await?;
+
+    let progress = ScanProgress::default();
+    tokio::select! {
+        newest = scanner.find_newest(&discord, channels, &dote_role, &progress) => newest,
+        () = report_progress(ctx, command, color, &progress) => None,
+    }
+}
+
+/// Builds the search depth from the `depth` and `since` options. Giving only `since` searches
+/// as far back as [`MAX_DEPTH`] allows, since the day limit is what the user cares about.
+fn scan_depth(command: &ApplicationCommandInteraction) -> ScanDepth {
+    let since_days = match option_value(command, "since") {
+        Some(CommandDataOptionValue::Integer(days)) => Some((*days).clamp(1, MAX_SINCE_DAYS)),
+        _ => None,
+    };
+    let messages = match option_value(command, "depth") {
+        Some(CommandDataOptionValue::Integer(depth)) => (*depth).clamp(1, MAX_DEPTH as i64) as u64,
+        _ if since_days.is_some() => MAX_DEPTH,
+        _ => DEFAULT_DEPTH,
+    };
+    ScanDepth {
+        messages,
+        since: since_days.map(|days| Utc::now() - chrono::Duration::days(days)),
+    }
+}
+
+/// Describes the limits a scan ran under, e.g. "within the last 100 messages of each channel".
+fn describe_depth(depth: ScanDepth) -> String {
+    let messages = format!(
+        "within the last {} messages of each channel",
+        depth.messages
+    );
+    match depth.since {
+        Some(since) => {
+            let days = (Utc::now() - since).num_days();
+            format!(
+                "{} or the last {} day{}",
+                messages,
+                days,
+                if days == 1 { "" } else { "s" }
+            )
+        }
+        None => messages,
+    }
+}
+
+/// Periodically edits the deferred response with how many channels have been scanned.
+/// Never finishes on its own; it's dropped once the scan completes.
+async fn report_progress(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    color: i32,
+    progress: &ScanProgress,
+) {
+    loop {
+        tokio::time::sleep(PROGRESS_INTERVAL).await;
+        let (scanned, total) = progress.get();
+        if let Err(e) = command
+            .edit_original_interaction_response(&ctx.http, |response| {
+                response.embed(|embed| {
+                    embed
+                        .title("Last @Dote")
+                        .description(format!("Scanned {}/{} channels...", scanned, total))
+                        .color(color)
+                })
+            })
+            .await
+        {
+            eprintln!("Error editing interaction response with progress: {:?}", e);
+        }
+    }
+}
+
+/// Describes the ping, with its time in the user's own timezone if they've set one.
+fn describe_ping(message: &MessageInfo, prefs: &UserPrefs) -> String {
+    let timestamp = message.timestamp;
+    let sent_at = NaiveDateTime::from_timestamp(timestamp, 0);
+    let elapsed = Utc::now().naive_utc().signed_duration_since(sent_at);
+    let local = match prefs.timezone {
+        Some(_) => format!(" ({})", prefs.format_time(DateTime::from_utc(sent_at, Utc))),
+        None => String::new(),
+    };
+    format!(
+        "Last @Dote message was at <t:{}:T>{} ({:.02} days ago, from user <@{}>)",
+        timestamp,
+        local,
+        (elapsed.num_seconds() as f64) / (60.0 * 60.0 * 24.0),
+        message.author_id
+    )
+}
+
+fn join_errors(errors: &[String]) -> String {
+    match errors {
+        [error] => error.clone(),
+        _ => format!(
+            "There were errors:\n{}",
+            errors
+                .iter()
+                .map(|content| format!("\t{}", content))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ),
+    }
+}
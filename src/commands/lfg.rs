@@ -0,0 +1,242 @@
+use chrono::{Duration, Utc};
+use serenity::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateInteractionResponseData};
+use serenity::futures::lock::Mutex;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::{MessageId, RoleId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration as StdDuration;
+
+use super::{guild_id, option_value, CommandError, CommandResult, SlashCommand};
+use crate::discord::MessageInfo;
+use crate::mentions::mention_list;
+use crate::party::{parse_start, Party, PARTY_SIZE};
+use crate::ping_history::SharedPingHistory;
+use crate::user_prefs::SharedPrefs;
+
+/// Minutes after any @Dote ping in which `/lfg` posts without pinging the role again, in
+/// guilds without a ping cooldown of their own.
+pub const LFG_PING_COOLDOWN_MINS: u64 = 30;
+/// Parties stop taking clicks once their message is this old.
+const PARTY_EXPIRY_HOURS: i64 = 12;
+const PARTY_COLOR: i32 = 0x2ECC71;
+
+/// `/lfg`: gathers a party with the @Dote role and buttons to join.
+pub struct Lfg {
+    prefs: SharedPrefs,
+    history: SharedPingHistory,
+    /// Open parties by the message showing them.
+    parties: Mutex<HashMap<MessageId, Party>>,
+}
+
+impl Lfg {
+    pub fn new(prefs: SharedPrefs, history: SharedPingHistory) -> Self {
+        Self {
+            prefs,
+            history,
+            parties: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl SlashCommand for Lfg {
+    fn name(&self) -> &'static str {
+        "lfg"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Pings @Dote to gather a party")
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("time")
+                    .description("When to start, as HH:MM in your /prefs timezone (default now)")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("slots")
+                    .description("Players wanted including you (default 5)")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(2)
+                    .max_int_value(PARTY_SIZE)
+                    .required(false)
+            })
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let guild_id = guild_id(command)?;
+        let role = match crate::GUILD_DOTE_ROLES.get(&guild_id) {
+            Some(role) => RoleId(*role),
+            None => {
+                return Err(CommandError::Message(format!(
+                    "Could not find dote role for guild {}",
+                    guild_id
+                )))
+            }
+        };
+        let now = Utc::now();
+        let starts_at = match option_value(command, "time") {
+            Some(CommandDataOptionValue::String(time)) => {
+                let prefs = self.prefs.lock().await.get(command.user.id);
+                parse_start(time, &prefs, now).map_err(CommandError::Message)?
+            }
+            _ => None,
+        };
+        let slots = match option_value(command, "slots") {
+            Some(CommandDataOptionValue::Integer(slots)) => *slots as usize,
+            _ => PARTY_SIZE,
+        };
+        let party = Party::new(command.user.id, slots, starts_at);
+
+        let history = self.history.lock().await;
+        let cooldown = history
+            .cooldown(guild_id)
+            .unwrap_or(StdDuration::from_secs(LFG_PING_COOLDOWN_MINS * 60));
+        let recent_ping = history
+            .within(guild_id, cooldown, now.timestamp())
+            .map(|last| last.timestamp);
+        drop(history);
+        let pings_role = recent_ping.is_none();
+        let content = match recent_ping {
+            Some(timestamp) => format!(
+                "@Dote was already pinged <t:{}:R>, so it isn't pinged again",
+                timestamp
+            ),
+            None => format!("<@&{}>", role),
+        };
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        party_message(message, &party)
+                            .content(&content)
+                            .allowed_mentions(|mentions| {
+                                let mentions = mentions.empty_parse();
+                                if pings_role {
+                                    mentions.roles(vec![role]);
+                                }
+                                mentions
+                            })
+                    })
+            })
+            .await?;
+
+        let message = command.get_interaction_response(&ctx.http).await?;
+        if pings_role {
+            let ping = MessageInfo {
+                author_id: command.user.id,
+                ..MessageInfo::from(message.clone())
+            };
+            self.history.lock().await.record(guild_id, ping);
+        }
+        let expiry = (now - Duration::hours(PARTY_EXPIRY_HOURS)).timestamp();
+        let mut parties = self.parties.lock().await;
+        parties.retain(|id, _| id.created_at().unix_timestamp() > expiry);
+        parties.insert(message.id, party);
+        Ok(())
+    }
+
+    async fn component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+    ) -> CommandResult {
+        let user_id = interaction.user.id;
+        let mut parties = self.parties.lock().await;
+        let party = parties
+            .get_mut(&interaction.message.id)
+            .ok_or_else(|| CommandError::Message(String::from("This party is closed")))?;
+        match interaction.data.custom_id.as_str() {
+            "lfg:join" => party.join(user_id),
+            "lfg:leave" => party.leave(user_id),
+            "lfg:maybe" => party.maybe(user_id),
+            other => {
+                return Err(CommandError::Message(format!(
+                    "Unknown party button {}",
+                    other
+                )))
+            }
+        };
+        let party = party.clone();
+        if party.is_full() {
+            parties.remove(&interaction.message.id);
+        }
+        drop(parties);
+
+        interaction
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|message| party_message(message, &party))
+            })
+            .await?;
+
+        if party.is_full() {
+            if let Err(e) = interaction
+                .create_followup_message(&ctx.http, |message| {
+                    message
+                        .content(format!("Party's full: {}", mention_list(&party.joined)))
+                        .allowed_mentions(|mentions| {
+                            mentions.empty_parse().users(party.joined.iter().copied())
+                        })
+                })
+                .await
+            {
+                eprintln!("Error announcing full party: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fills in the party's embed and buttons, which are disabled once it's full.
+fn party_message<'a, 'b>(
+    message: &'b mut CreateInteractionResponseData<'a>,
+    party: &Party,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    let full = party.is_full();
+    message
+        .embed(|embed| {
+            embed
+                .title(if full {
+                    "Party full"
+                } else {
+                    "Looking for party"
+                })
+                .description(party.describe())
+                .color(PARTY_COLOR)
+        })
+        .components(|components| {
+            components.create_action_row(|row| {
+                for (action, label, style) in [
+                    ("join", "Join", ButtonStyle::Success),
+                    ("maybe", "Maybe", ButtonStyle::Secondary),
+                    ("leave", "Leave", ButtonStyle::Danger),
+                ] {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("lfg:{}", action))
+                            .label(label)
+                            .style(style)
+                            .disabled(full)
+                    });
+                }
+                row
+            })
+        })
+}
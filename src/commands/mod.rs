@@ -0,0 +1,310 @@
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::Command;
+use serenity::model::application::interaction::{
+    application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+    message_component::MessageComponentInteraction,
+    InteractionResponseType,
+};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::fmt;
+
+use crate::archive::SharedArchive;
+use crate::ping_history::SharedPingHistory;
+use crate::subscriptions::SharedSubscriptions;
+use crate::user_prefs::SharedPrefs;
+
+mod last_ping;
+mod lfg;
+mod patches;
+mod prefs;
+mod subscribe;
+
+pub use last_ping::LastPing;
+pub use lfg::{Lfg, LFG_PING_COOLDOWN_MINS};
+pub use patches::{PatchSearch, Patches};
+pub use prefs::Prefs;
+pub use subscribe::{Subscribe, Unsubscribe};
+
+const ERROR_COLOR: i32 = 0xFF0000;
+
+#[derive(Debug)]
+pub enum CommandError {
+    /// The command was used in a DM but needs a guild.
+    NotInGuild,
+    /// A failure to show the user as is.
+    Message(String),
+    /// A request to Discord failed. Boxed since serenity's error is large.
+    Discord(Box<SerenityError>),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NotInGuild => write!(f, "This command can only be used in a server"),
+            CommandError::Message(message) => write!(f, "{}", message),
+            CommandError::Discord(e) => write!(f, "Error talking to Discord: {}", e),
+        }
+    }
+}
+
+impl From<SerenityError> for CommandError {
+    fn from(e: SerenityError) -> Self {
+        CommandError::Discord(Box::new(e))
+    }
+}
+
+pub type CommandResult = Result<(), CommandError>;
+
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    /// Name the command is registered and dispatched under.
+    fn name(&self) -> &'static str;
+
+    /// Fills in the rest of the command's definition (description, options, ...).
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand;
+
+    /// Whether the dispatcher should acknowledge the interaction with a deferred response
+    /// before calling [`SlashCommand::run`]. Commands that may take longer than Discord's
+    /// 3 second window should set this and edit the original response instead.
+    fn deferred(&self) -> bool {
+        false
+    }
+
+    /// Runs the command. Errors are reported back to the user by the dispatcher, so
+    /// implementations should only respond on success.
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult;
+
+    /// Handles a click on a component of a message the command sent. Components are routed
+    /// here when their custom id is the command's name followed by a colon, e.g. "lfg:join".
+    async fn component(
+        &self,
+        _ctx: &Context,
+        _interaction: &MessageComponentInteraction,
+    ) -> CommandResult {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<Box<dyn SlashCommand>>,
+}
+
+impl CommandRegistry {
+    pub fn register(mut self, command: impl SlashCommand + 'static) -> Self {
+        self.commands.push(Box::new(command));
+        self
+    }
+
+    /// Replaces the bot's global commands with the ones in this registry.
+    pub async fn install(&self, ctx: &Context) {
+        match Command::set_global_application_commands(&ctx.http, |commands| {
+            for command in &self.commands {
+                commands.create_application_command(|definition| {
+                    command.define(definition.name(command.name()))
+                });
+            }
+            commands
+        })
+        .await
+        {
+            Ok(commands) => {
+                for c in commands {
+                    println!("Added command: {}", c.name);
+                }
+            }
+            Err(e) => eprintln!("Error adding commands: {:?}", e),
+        }
+    }
+
+    pub async fn dispatch(&self, ctx: &Context, command: &ApplicationCommandInteraction) {
+        let handler = match self
+            .commands
+            .iter()
+            .find(|handler| handler.name() == command.data.name)
+        {
+            Some(handler) => handler,
+            None => {
+                eprintln!("Received unknown command {}", command.data.name);
+                return;
+            }
+        };
+
+        if handler.deferred() {
+            if let Err(e) = command
+                .create_interaction_response(&ctx.http, |response| {
+                    response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                })
+                .await
+            {
+                eprintln!("Error deferring interaction response: {:?}", e);
+                return;
+            }
+        }
+
+        if let Err(error) = handler.run(ctx, command).await {
+            eprintln!("Error running command {}: {}", command.data.name, error);
+            if handler.deferred() {
+                // The deferred response is public, so replace it with an ephemeral followup
+                // rather than showing the error to the whole channel.
+                if let Err(e) = command
+                    .delete_original_interaction_response(&ctx.http)
+                    .await
+                {
+                    eprintln!("Error deleting interaction response: {:?}", e);
+                }
+                followup_error(ctx, command, "Error", &error.to_string()).await;
+            } else {
+                respond_error(ctx, command, &error.to_string()).await;
+            }
+        }
+    }
+
+    pub async fn dispatch_component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+    ) {
+        let name = interaction
+            .data
+            .custom_id
+            .split_once(':')
+            .map_or(interaction.data.custom_id.as_str(), |(name, _)| name);
+        let handler = match self.commands.iter().find(|handler| handler.name() == name) {
+            Some(handler) => handler,
+            None => {
+                eprintln!("Received unknown component {}", interaction.data.custom_id);
+                return;
+            }
+        };
+
+        if let Err(error) = handler.component(ctx, interaction).await {
+            eprintln!(
+                "Error handling component {}: {}",
+                interaction.data.custom_id, error
+            );
+            respond_component_error(ctx, interaction, &error.to_string()).await;
+        }
+    }
+}
+
+/// Every command the bot offers.
+pub fn registry(
+    archive: SharedArchive,
+    subscriptions: SharedSubscriptions,
+    prefs: SharedPrefs,
+    history: SharedPingHistory,
+    scan_concurrency: usize,
+) -> CommandRegistry {
+    CommandRegistry::default()
+        .register(LastPing {
+            prefs: prefs.clone(),
+            history: history.clone(),
+            scan_concurrency,
+        })
+        .register(Lfg::new(prefs.clone(), history))
+        .register(Patches {
+            archive: archive.clone(),
+        })
+        .register(PatchSearch { archive })
+        .register(Subscribe {
+            subscriptions: subscriptions.clone(),
+        })
+        .register(Unsubscribe { subscriptions })
+        .register(Prefs { prefs })
+}
+
+pub fn guild_id(command: &ApplicationCommandInteraction) -> Result<GuildId, CommandError> {
+    command.guild_id.ok_or(CommandError::NotInGuild)
+}
+
+pub fn option_value<'a>(
+    command: &'a ApplicationCommandInteraction,
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    command
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.resolved.as_ref())
+}
+
+/// Sends an ephemeral error embed as a followup to an already acknowledged interaction.
+pub async fn followup_error(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    title: &str,
+    description: &str,
+) {
+    if let Err(e) = command
+        .create_followup_message(&ctx.http, |message| {
+            message
+                .embed(|embed| {
+                    embed
+                        .title(title)
+                        .description(description)
+                        .color(ERROR_COLOR)
+                })
+                .ephemeral(true)
+        })
+        .await
+    {
+        eprintln!("Error adding followup message: {:?}", e);
+    }
+}
+
+async fn respond_error(ctx: &Context, command: &ApplicationCommandInteraction, description: &str) {
+    if let Err(e) = command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message
+                        .embed(|embed| {
+                            embed
+                                .title("Error")
+                                .description(description)
+                                .color(ERROR_COLOR)
+                        })
+                        .ephemeral(true)
+                })
+        })
+        .await
+    {
+        // The command may have responded before failing, in which case only a followup works
+        eprintln!("Error sending error response, trying followup: {:?}", e);
+        followup_error(ctx, command, "Error", description).await;
+    }
+}
+
+async fn respond_component_error(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    description: &str,
+) {
+    if let Err(e) = interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message
+                        .embed(|embed| {
+                            embed
+                                .title("Error")
+                                .description(description)
+                                .color(ERROR_COLOR)
+                        })
+                        .ephemeral(true)
+                })
+        })
+        .await
+    {
+        eprintln!("Error sending component error response: {:?}", e);
+    }
+}
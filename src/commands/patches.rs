@@ -0,0 +1,180 @@
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::prelude::*;
+
+use super::{option_value, CommandError, CommandResult, SlashCommand};
+use crate::archive::{ArchivedPatch, SharedArchive};
+
+const PATCHES_PER_PAGE: usize = 10;
+/// Matches listed under the best one in `/patch`.
+const OTHER_MATCHES: usize = 4;
+/// Characters of a patch's contents shown by `/patch`.
+const PREVIEW_LENGTH: usize = 300;
+const PATCH_COLOR: i32 = 0xA72714;
+
+/// `/patches`: lists archived patches, newest first.
+pub struct Patches {
+    pub archive: SharedArchive,
+}
+
+#[async_trait]
+impl SlashCommand for Patches {
+    fn name(&self) -> &'static str {
+        "patches"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Lists recently announced patches")
+            .create_option(|option| {
+                option
+                    .name("page")
+                    .description("Page of older patches to show (default 1)")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let page = match option_value(command, "page") {
+            Some(CommandDataOptionValue::Integer(page)) => (*page).max(1) as usize,
+            _ => 1,
+        };
+
+        let archive = self.archive.lock().await;
+        if archive.is_empty() {
+            return Err(CommandError::Message(String::from(
+                "No patches have been announced yet",
+            )));
+        }
+        let pages = archive.page_count(PATCHES_PER_PAGE);
+        if page > pages {
+            return Err(CommandError::Message(format!(
+                "There are only {} pages of patches",
+                pages
+            )));
+        }
+        let description = archive
+            .page(page, PATCHES_PER_PAGE)
+            .iter()
+            .map(patch_line)
+            .collect::<Vec<String>>()
+            .join("\n");
+        drop(archive);
+
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message.embed(|embed| {
+                            embed
+                                .title("Patches")
+                                .description(description)
+                                .footer(|footer| footer.text(format!("Page {}/{}", page, pages)))
+                                .color(PATCH_COLOR)
+                        })
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// `/patch`: finds an archived patch by its title or contents.
+pub struct PatchSearch {
+    pub archive: SharedArchive,
+}
+
+#[async_trait]
+impl SlashCommand for PatchSearch {
+    fn name(&self) -> &'static str {
+        "patch"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Finds an announced patch")
+            .create_option(|option| {
+                option
+                    .name("query")
+                    .description("Words from the patch's title or notes, e.g. 7.33b")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let query = match option_value(command, "query") {
+            Some(CommandDataOptionValue::String(query)) => query.clone(),
+            _ => return Err(CommandError::Message(String::from("No query given"))),
+        };
+
+        let archive = self.archive.lock().await;
+        let matches: Vec<ArchivedPatch> = archive
+            .search(&query, OTHER_MATCHES + 1)
+            .into_iter()
+            .cloned()
+            .collect();
+        drop(archive);
+        let (best, others) = match matches.split_first() {
+            Some(found) => found,
+            None => {
+                return Err(CommandError::Message(format!(
+                    "No patches match {:?}",
+                    query
+                )))
+            }
+        };
+
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message.embed(|embed| {
+                            embed
+                                .title(&best.title)
+                                .url(&best.url)
+                                .description(format!(
+                                    "Published <t:{}:D>\n\n{}",
+                                    best.date,
+                                    preview(&best.contents)
+                                ))
+                                .color(PATCH_COLOR);
+                            if !others.is_empty() {
+                                let lines: Vec<String> = others.iter().map(patch_line).collect();
+                                embed.field("Other matches", lines.join("\n"), false);
+                            }
+                            embed
+                        })
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+fn patch_line(patch: &ArchivedPatch) -> String {
+    format!("<t:{}:d> [{}]({})", patch.date, patch.title, patch.url)
+}
+
+fn preview(contents: &str) -> String {
+    if contents.chars().count() <= PREVIEW_LENGTH {
+        return contents.to_string();
+    }
+    let cut: String = contents.chars().take(PREVIEW_LENGTH).collect();
+    format!("{}…", cut.trim_end())
+}
@@ -0,0 +1,100 @@
+use chrono::Utc;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::prelude::*;
+
+use super::{option_value, CommandError, CommandResult, SlashCommand};
+use crate::poll_schedule::TimeWindow;
+use crate::user_prefs::{format_utc_offset, SharedPrefs, Timezone, UserPrefs};
+
+/// `/prefs`: sets the user's timezone and the hours they don't want to be pinged.
+pub struct Prefs {
+    pub prefs: SharedPrefs,
+}
+
+#[async_trait]
+impl SlashCommand for Prefs {
+    fn name(&self) -> &'static str {
+        "prefs"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Shows or sets your timezone and quiet hours")
+            .create_option(|option| {
+                option
+                    .name("timezone")
+                    .description("Your timezone, e.g. Europe/Berlin, or offset from UTC, e.g. UTC+10")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("quiet")
+                    .description("Local hours not to ping you in, e.g. 22:00-08:00, or off")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let mut store = self.prefs.lock().await;
+        let mut prefs = store.get(command.user.id);
+        let mut changed = false;
+        if let Some(CommandDataOptionValue::String(timezone)) = option_value(command, "timezone") {
+            prefs.timezone = Some(timezone.parse().map_err(CommandError::Message)?);
+            changed = true;
+        }
+        if let Some(CommandDataOptionValue::String(quiet)) = option_value(command, "quiet") {
+            prefs.quiet_hours = match quiet.trim().to_ascii_lowercase().as_str() {
+                "off" | "none" => None,
+                _ => Some(quiet.parse::<TimeWindow>().map_err(CommandError::Message)?),
+            };
+            changed = true;
+        }
+        if changed {
+            store
+                .set(command.user.id, prefs)
+                .map_err(CommandError::Message)?;
+        }
+        drop(store);
+
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message.content(describe_prefs(&prefs)).ephemeral(true)
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+fn describe_prefs(prefs: &UserPrefs) -> String {
+    let quiet = match prefs.quiet_hours {
+        Some(window) => format!(
+            "{} (pings wait until they're over, and patch mentions don't notify you)",
+            window
+        ),
+        None => String::from("off"),
+    };
+    let timezone = match prefs.timezone() {
+        Timezone::Named(tz) => format!(
+            "{} ({} now)",
+            tz.name(),
+            format_utc_offset(prefs.timezone().offset_at(Utc::now()))
+        ),
+        timezone => timezone.to_string(),
+    };
+    format!("Timezone: {}\nQuiet hours: {}", timezone, quiet)
+}
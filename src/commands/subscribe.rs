@@ -0,0 +1,146 @@
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOptionValue,
+};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::prelude::*;
+
+use super::{guild_id, option_value, CommandError, CommandResult, SlashCommand};
+use crate::heroes::find_hero;
+use crate::subscriptions::SharedSubscriptions;
+
+/// `/subscribe`: mentions the user when patch notes change a hero.
+pub struct Subscribe {
+    pub subscriptions: SharedSubscriptions,
+}
+
+#[async_trait]
+impl SlashCommand for Subscribe {
+    fn name(&self) -> &'static str {
+        "subscribe"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Get mentioned when a patch changes a hero")
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("hero")
+                    .description("The hero to follow, e.g. Anti-Mage")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let guild_id = guild_id(command)?;
+        let hero = hero_option(command)?;
+
+        let mut subscriptions = self.subscriptions.lock().await;
+        let added = subscriptions
+            .subscribe(guild_id, command.user.id, hero)
+            .map_err(CommandError::Message)?;
+        let heroes = subscriptions.heroes(guild_id, command.user.id);
+        drop(subscriptions);
+
+        let status = if added {
+            format!("You'll be mentioned when a patch changes {}.", hero)
+        } else {
+            format!("You're already following {}.", hero)
+        };
+        respond(
+            ctx,
+            command,
+            format!("{}\nFollowing: {}", status, heroes.join(", ")),
+        )
+        .await
+    }
+}
+
+/// `/unsubscribe`: stops mentioning the user about a hero.
+pub struct Unsubscribe {
+    pub subscriptions: SharedSubscriptions,
+}
+
+#[async_trait]
+impl SlashCommand for Unsubscribe {
+    fn name(&self) -> &'static str {
+        "unsubscribe"
+    }
+
+    fn define<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .description("Stop getting mentioned about a hero's changes")
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("hero")
+                    .description("The hero to stop following")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+    }
+
+    async fn run(&self, ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+        let guild_id = guild_id(command)?;
+        let hero = hero_option(command)?;
+
+        let mut subscriptions = self.subscriptions.lock().await;
+        let removed = subscriptions
+            .unsubscribe(guild_id, command.user.id, hero)
+            .map_err(CommandError::Message)?;
+        let heroes = subscriptions.heroes(guild_id, command.user.id);
+        drop(subscriptions);
+
+        if !removed {
+            return Err(CommandError::Message(format!(
+                "You aren't following {}",
+                hero
+            )));
+        }
+        let following = if heroes.is_empty() {
+            String::from("nobody")
+        } else {
+            heroes.join(", ")
+        };
+        respond(
+            ctx,
+            command,
+            format!("Stopped following {}.\nFollowing: {}", hero, following),
+        )
+        .await
+    }
+}
+
+fn hero_option(command: &ApplicationCommandInteraction) -> Result<&'static str, CommandError> {
+    let name = match option_value(command, "hero") {
+        Some(CommandDataOptionValue::String(name)) => name,
+        _ => return Err(CommandError::Message(String::from("No hero given"))),
+    };
+    find_hero(name)
+        .ok_or_else(|| CommandError::Message(format!("There's no hero named {:?}", name)))
+}
+
+async fn respond(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    content: String,
+) -> CommandResult {
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.content(content).ephemeral(true))
+        })
+        .await?;
+    Ok(())
+}
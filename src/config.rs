@@ -0,0 +1,496 @@
+use regex::Regex;
+use serde::Deserialize;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::feed_routes::{AnnounceRole, FeedRule, VoiceNotice};
+use crate::playing::{GameMatcher, DOTA_APPLICATION_ID, DOTA_NAME_PATTERN};
+use crate::poll_schedule::TimeWindow;
+use crate::webhooks::{Webhook, WebhookKind};
+
+/// Config file read when `CONFIG_FILE` isn't set. It's fine for it not to exist.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 60;
+const DEFAULT_POLL_JITTER_SECS: u64 = 0;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_STEAM_API_URL: &str = "http://api.steampowered.com";
+const DEFAULT_NEWS_COUNT: u32 = 10;
+const DEFAULT_NEWS_MAXLENGTH: u32 = 0;
+const DEFAULT_PATCH_WINDOW_POLL_INTERVAL_SECS: u64 = 20;
+const DEFAULT_MAX_BACKOFF_SECS: u64 = 900;
+const DEFAULT_PATCH_ARCHIVE: &str = "patches.json";
+const DEFAULT_SUBSCRIPTIONS: &str = "subscriptions.json";
+const DEFAULT_USER_PREFS: &str = "user_prefs.json";
+const DEFAULT_SCAN_CONCURRENCY: usize = 8;
+
+/// Most news items Steam will return in one request.
+const MAX_NEWS_COUNT: u32 = 100;
+
+/// Settings for the bot, read from an optional TOML file and overridden by environment
+/// variables.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub discord_token: String,
+    /// Time between polls of the Steam news feed.
+    pub poll_interval: Duration,
+    /// Up to this much extra time is randomly added to each poll interval.
+    pub poll_jitter: Duration,
+    /// How long a request to Steam may take before it's abandoned.
+    pub request_timeout: Duration,
+    /// Base URL of the Steam Web API, without a trailing slash.
+    pub steam_api_url: String,
+    /// Number of news items requested per poll.
+    pub news_count: u32,
+    /// Maximum length of each news item's contents. 0 returns the full contents.
+    pub news_maxlength: u32,
+    /// Daily UTC window when patches usually ship, polled at `patch_window_poll_interval`.
+    pub patch_window: Option<TimeWindow>,
+    pub patch_window_poll_interval: Duration,
+    /// Longest wait between polls while backing off from errors.
+    pub max_backoff: Duration,
+    /// JSON file every announced article is kept in.
+    pub patch_archive: PathBuf,
+    /// JSON file the heroes users `/subscribe` to are kept in.
+    pub subscriptions: PathBuf,
+    /// JSON file users' `/prefs` are kept in.
+    pub user_prefs: PathBuf,
+    /// Most channels `/lastping` reads at once.
+    pub scan_concurrency: usize,
+    /// News routes for each guild. Guilds without any get patch notes in their updates channel.
+    pub routes: HashMap<GuildId, Vec<FeedRule>>,
+    /// Role mentioned with each guild's patch announcements, if any.
+    pub announce_roles: HashMap<GuildId, AnnounceRole>,
+    /// Where announcements are posted outside of Discord guild channels.
+    pub webhooks: Vec<Webhook>,
+    /// Guilds whose updates are crossposted to followers when posted in an announcement channel.
+    pub crosspost_guilds: HashSet<GuildId>,
+    /// How members running the game are recognized for restart pings.
+    pub game: GameMatcher,
+    /// Guilds that also tell players in voice channels to restart there.
+    pub voice_notices: HashMap<GuildId, VoiceNotice>,
+    /// How long members of each guild must wait between @Dote pings.
+    pub ping_cooldowns: HashMap<GuildId, Duration>,
+}
+
+/// The TOML file's layout. Everything is optional since environment variables can fill it in.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    discord_token: Option<String>,
+    poll_interval_secs: Option<u64>,
+    poll_jitter_secs: Option<u64>,
+    request_timeout_secs: Option<u64>,
+    steam_api_url: Option<String>,
+    news_count: Option<u32>,
+    news_maxlength: Option<u32>,
+    patch_window_utc: Option<String>,
+    patch_window_poll_interval_secs: Option<u64>,
+    max_backoff_secs: Option<u64>,
+    patch_archive: Option<PathBuf>,
+    subscriptions: Option<PathBuf>,
+    user_prefs: Option<PathBuf>,
+    scan_concurrency: Option<usize>,
+    #[serde(default)]
+    routes: Vec<RouteConfig>,
+    #[serde(default)]
+    announce_roles: Vec<AnnounceRoleConfig>,
+    #[serde(default)]
+    voice_notices: Vec<VoiceNoticeConfig>,
+    #[serde(default)]
+    ping_cooldowns: Vec<PingCooldownConfig>,
+    #[serde(default)]
+    webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    crosspost_guilds: Vec<u64>,
+    /// Discord application ids of the game. Defaults to Dota 2's.
+    game_application_ids: Option<Vec<u64>>,
+    /// Regexes for the game's activity name. Defaults to "Dota 2".
+    game_names: Option<Vec<String>>,
+}
+
+/// A `[[routes]]` table. These can only be set in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteConfig {
+    guild: u64,
+    channel: u64,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    feeds: Vec<String>,
+    title: Option<String>,
+    #[serde(default)]
+    restart_ping: bool,
+}
+
+/// An `[[announce_roles]]` table. These can only be set in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnnounceRoleConfig {
+    guild: u64,
+    role: u64,
+    #[serde(default)]
+    minor: bool,
+}
+
+/// A `[[voice_notices]]` table. These can only be set in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VoiceNoticeConfig {
+    guild: u64,
+    #[serde(default)]
+    replace_ping: bool,
+}
+
+/// A `[[ping_cooldowns]]` table. These can only be set in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PingCooldownConfig {
+    guild: u64,
+    minutes: u64,
+}
+
+/// A `[[webhooks]]` table. These can only be set in the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookConfig {
+    /// Only this guild's announcements are sent. All of them if unset.
+    guild: Option<u64>,
+    url: String,
+    /// `discord`, `json` or `slack`.
+    kind: String,
+    username: Option<String>,
+    avatar_url: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file exists but couldn't be read.
+    Read(PathBuf, std::io::Error),
+    /// The config file isn't valid TOML or has unknown or mistyped keys.
+    Parse(PathBuf, toml::de::Error),
+    /// An environment variable couldn't be parsed.
+    Env(&'static str, String),
+    /// A required setting wasn't given anywhere.
+    Missing(&'static str),
+    /// A setting was given but isn't usable.
+    Invalid(&'static str, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => {
+                write!(f, "Failed to read config file {}: {}", path.display(), e)
+            }
+            ConfigError::Parse(path, e) => {
+                write!(f, "Failed to parse config file {}: {}", path.display(), e)
+            }
+            ConfigError::Env(name, e) => write!(f, "Invalid value for {}: {}", name, e),
+            ConfigError::Missing(name) => write!(
+                f,
+                "{} must be set in the environment or the config file",
+                name
+            ),
+            ConfigError::Invalid(name, e) => write!(f, "Invalid {}: {}", name, e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Loads the config file named by `CONFIG_FILE` (or `config.toml` if present), applies
+    /// environment overrides and validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match env::var("CONFIG_FILE") {
+            Ok(path) => read_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                read_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            Err(_) => FileConfig::default(),
+        };
+        Self::from_sources(file, |name| env::var(name).ok())
+    }
+
+    /// Loads config from TOML text and a lookup for environment variables.
+    pub fn from_toml(
+        toml: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let file =
+            toml::from_str(toml).map_err(|e| ConfigError::Parse(PathBuf::from("<inline>"), e))?;
+        Self::from_sources(file, env)
+    }
+
+    fn from_sources(
+        file: FileConfig,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let discord_token = env("DISCORD_TOKEN")
+            .or(file.discord_token)
+            .filter(|token| !token.is_empty())
+            .ok_or(ConfigError::Missing("DISCORD_TOKEN"))?;
+
+        let poll_interval_secs = env_parsed(&env, "POLL_INTERVAL_SECS")?
+            .or(file.poll_interval_secs)
+            .unwrap_or(DEFAULT_POLL_INTERVAL_SECS);
+        let poll_jitter_secs = env_parsed(&env, "POLL_JITTER_SECS")?
+            .or(file.poll_jitter_secs)
+            .unwrap_or(DEFAULT_POLL_JITTER_SECS);
+        let request_timeout_secs = env_parsed(&env, "REQUEST_TIMEOUT_SECS")?
+            .or(file.request_timeout_secs)
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS);
+        let steam_api_url = env("STEAM_API_URL")
+            .or(file.steam_api_url)
+            .unwrap_or_else(|| String::from(DEFAULT_STEAM_API_URL));
+        let news_count = env_parsed(&env, "NEWS_COUNT")?
+            .or(file.news_count)
+            .unwrap_or(DEFAULT_NEWS_COUNT);
+        let news_maxlength = env_parsed(&env, "NEWS_MAXLENGTH")?
+            .or(file.news_maxlength)
+            .unwrap_or(DEFAULT_NEWS_MAXLENGTH);
+        let patch_window = match env_parsed(&env, "PATCH_WINDOW_UTC")? {
+            Some(window) => Some(window),
+            None => match file.patch_window_utc {
+                Some(raw) => Some(
+                    raw.parse::<TimeWindow>()
+                        .map_err(|e| ConfigError::Invalid("patch window", e))?,
+                ),
+                None => None,
+            },
+        };
+        let patch_window_poll_interval_secs = env_parsed(&env, "PATCH_WINDOW_POLL_INTERVAL_SECS")?
+            .or(file.patch_window_poll_interval_secs)
+            .unwrap_or(DEFAULT_PATCH_WINDOW_POLL_INTERVAL_SECS);
+        let max_backoff_secs = env_parsed(&env, "MAX_BACKOFF_SECS")?
+            .or(file.max_backoff_secs)
+            .unwrap_or(DEFAULT_MAX_BACKOFF_SECS);
+        let patch_archive = env("PATCH_ARCHIVE")
+            .map(PathBuf::from)
+            .or(file.patch_archive)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PATCH_ARCHIVE));
+        let subscriptions = env("SUBSCRIPTIONS")
+            .map(PathBuf::from)
+            .or(file.subscriptions)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SUBSCRIPTIONS));
+        let user_prefs = env("USER_PREFS")
+            .map(PathBuf::from)
+            .or(file.user_prefs)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_USER_PREFS));
+        let scan_concurrency = env_parsed(&env, "SCAN_CONCURRENCY")?
+            .or(file.scan_concurrency)
+            .unwrap_or(DEFAULT_SCAN_CONCURRENCY);
+
+        if poll_interval_secs == 0 || patch_window_poll_interval_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "poll interval",
+                String::from("must be at least 1 second"),
+            ));
+        }
+        if max_backoff_secs < poll_interval_secs {
+            return Err(ConfigError::Invalid(
+                "max backoff",
+                format!(
+                    "must be at least the poll interval ({}s), got {}s",
+                    poll_interval_secs, max_backoff_secs
+                ),
+            ));
+        }
+        if request_timeout_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "request timeout",
+                String::from("must be at least 1 second"),
+            ));
+        }
+        if !(1..=MAX_NEWS_COUNT).contains(&news_count) {
+            return Err(ConfigError::Invalid(
+                "news count",
+                format!(
+                    "must be between 1 and {}, got {}",
+                    MAX_NEWS_COUNT, news_count
+                ),
+            ));
+        }
+        if scan_concurrency == 0 {
+            return Err(ConfigError::Invalid(
+                "scan concurrency",
+                String::from("must be at least 1"),
+            ));
+        }
+        let steam_api_url = validate_url(&steam_api_url)?;
+
+        let mut routes: HashMap<GuildId, Vec<FeedRule>> = HashMap::new();
+        for route in file.routes {
+            let title = match route.title {
+                Some(title) => Some(Regex::new(&title).map_err(|e| {
+                    ConfigError::Invalid("route title", format!("{:?} ({})", title, e))
+                })?),
+                None => None,
+            };
+            routes
+                .entry(GuildId(route.guild))
+                .or_default()
+                .push(FeedRule {
+                    channel_id: ChannelId(route.channel),
+                    tags: route.tags,
+                    feeds: route.feeds,
+                    title,
+                    restart_ping: route.restart_ping,
+                });
+        }
+        let announce_roles = file
+            .announce_roles
+            .into_iter()
+            .map(|role| {
+                (
+                    GuildId(role.guild),
+                    AnnounceRole {
+                        role_id: RoleId(role.role),
+                        minor: role.minor,
+                    },
+                )
+            })
+            .collect();
+        let voice_notices = file
+            .voice_notices
+            .into_iter()
+            .map(|notice| {
+                (
+                    GuildId(notice.guild),
+                    VoiceNotice {
+                        replace_ping: notice.replace_ping,
+                    },
+                )
+            })
+            .collect();
+        let mut ping_cooldowns = HashMap::new();
+        for cooldown in file.ping_cooldowns {
+            if cooldown.minutes == 0 {
+                return Err(ConfigError::Invalid(
+                    "ping cooldown",
+                    format!("must be at least 1 minute for guild {}", cooldown.guild),
+                ));
+            }
+            ping_cooldowns.insert(
+                GuildId(cooldown.guild),
+                Duration::from_secs(cooldown.minutes * 60),
+            );
+        }
+        let mut game_names = vec![];
+        for name in file
+            .game_names
+            .unwrap_or_else(|| vec![String::from(DOTA_NAME_PATTERN)])
+        {
+            game_names.push(
+                Regex::new(&name).map_err(|e| {
+                    ConfigError::Invalid("game name", format!("{:?} ({})", name, e))
+                })?,
+            );
+        }
+        let game = GameMatcher::new(
+            file.game_application_ids
+                .unwrap_or_else(|| vec![DOTA_APPLICATION_ID]),
+            game_names,
+        );
+        let mut webhooks = vec![];
+        for webhook in file.webhooks {
+            check_http_url("webhook URL", &webhook.url)?;
+            let kind = match webhook.kind.as_str() {
+                "discord" => WebhookKind::Discord {
+                    username: webhook.username,
+                    avatar_url: webhook.avatar_url,
+                },
+                "json" | "slack" if webhook.username.is_some() || webhook.avatar_url.is_some() => {
+                    return Err(ConfigError::Invalid(
+                        "webhook",
+                        String::from("username and avatar_url only apply to discord webhooks"),
+                    ));
+                }
+                "json" => WebhookKind::Json,
+                "slack" => WebhookKind::Slack,
+                other => {
+                    return Err(ConfigError::Invalid(
+                        "webhook kind",
+                        format!("{:?} must be discord, json or slack", other),
+                    ));
+                }
+            };
+            webhooks.push(Webhook {
+                guild_id: webhook.guild.map(GuildId),
+                url: webhook.url,
+                kind,
+            });
+        }
+
+        Ok(Self {
+            discord_token,
+            poll_interval: Duration::from_secs(poll_interval_secs),
+            poll_jitter: Duration::from_secs(poll_jitter_secs),
+            request_timeout: Duration::from_secs(request_timeout_secs),
+            steam_api_url,
+            news_count,
+            news_maxlength,
+            patch_window,
+            patch_window_poll_interval: Duration::from_secs(patch_window_poll_interval_secs),
+            max_backoff: Duration::from_secs(max_backoff_secs),
+            patch_archive,
+            subscriptions,
+            user_prefs,
+            scan_concurrency,
+            routes,
+            announce_roles,
+            webhooks,
+            crosspost_guilds: file.crosspost_guilds.into_iter().map(GuildId).collect(),
+            game,
+            voice_notices,
+            ping_cooldowns,
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<FileConfig, ConfigError> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+    toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+}
+
+fn env_parsed<T: std::str::FromStr>(
+    env: &impl Fn(&str) -> Option<String>,
+    name: &'static str,
+) -> Result<Option<T>, ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    match env(name) {
+        Some(raw) => raw
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| ConfigError::Env(name, format!("{:?} ({})", raw, e))),
+        None => Ok(None),
+    }
+}
+
+fn validate_url(raw: &str) -> Result<String, ConfigError> {
+    check_http_url("Steam API URL", raw)?;
+    Ok(raw.trim_end_matches('/').to_string())
+}
+
+fn check_http_url(setting: &'static str, raw: &str) -> Result<(), ConfigError> {
+    let url = reqwest::Url::parse(raw)
+        .map_err(|e| ConfigError::Invalid(setting, format!("{:?} ({})", raw, e)))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(ConfigError::Invalid(
+            setting,
+            format!("{:?} must be http or https", raw),
+        ));
+    }
+    Ok(())
+}
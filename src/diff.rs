@@ -0,0 +1,68 @@
+/// A line that differs between two versions of a text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange<'a> {
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+/// The lines removed from `old` and added in `new`, in order, ignoring trailing whitespace.
+pub fn line_changes<'a>(old: &'a str, new: &'a str) -> Vec<LineChange<'a>> {
+    let old: Vec<&str> = old.lines().map(str::trim_end).collect();
+    let new: Vec<&str> = new.lines().map(str::trim_end).collect();
+
+    // lcs[i][j] is the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals first, as diffs usually show them
+            changes.push(LineChange::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Added(new[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// `changes` as a `diff` code block no longer than about `max_length` characters, noting how
+/// many lines were left out if they don't all fit.
+pub fn render_diff(changes: &[LineChange], max_length: usize) -> String {
+    let mut body = String::new();
+    let mut shown = 0;
+    for change in changes {
+        let line = match change {
+            LineChange::Added(line) => format!("+ {}\n", line),
+            LineChange::Removed(line) => format!("- {}\n", line),
+        };
+        if body.len() + line.len() > max_length {
+            break;
+        }
+        body.push_str(&line);
+        shown += 1;
+    }
+
+    let mut diff = format!("```diff\n{}```", body);
+    if shown < changes.len() {
+        diff.push_str(&format!(
+            "\n…and {} more changed lines",
+            changes.len() - shown
+        ));
+    }
+    diff
+}
@@ -0,0 +1,386 @@
+use chrono::{DateTime, Utc};
+use serenity::async_trait;
+use serenity::cache::Cache;
+use serenity::http::Http;
+use serenity::model::channel::{Channel, ChannelType, GuildChannel, Message};
+use serenity::model::gateway::{Activity, Presence};
+use serenity::model::guild::Guild;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::members::{users_to_look_up, MemberCoverage, UserLookups};
+
+/// Most users [`SerenityDiscord`] looks up in one go before a ping, to stay clear of rate
+/// limits.
+pub const MAX_USER_LOOKUPS: usize = 50;
+
+/// The parts of a guild channel or thread the bot cares about.
+#[derive(Clone, Debug)]
+pub struct ChannelInfo {
+    pub id: ChannelId,
+    pub kind: ChannelType,
+    pub last_message_id: Option<MessageId>,
+    /// Unix timestamp of when the thread was archived, if it's an archived thread.
+    pub archived_at: Option<i64>,
+}
+
+impl From<GuildChannel> for ChannelInfo {
+    fn from(channel: GuildChannel) -> Self {
+        Self {
+            id: channel.id,
+            kind: channel.kind,
+            last_message_id: channel.last_message_id,
+            archived_at: channel
+                .thread_metadata
+                .filter(|metadata| metadata.archived)
+                .and_then(|metadata| metadata.archive_timestamp)
+                .map(|archived_at| archived_at.unix_timestamp()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MessageInfo {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    pub author_id: UserId,
+    pub content: String,
+    /// Unix timestamp of when the message was sent.
+    pub timestamp: i64,
+}
+
+impl From<Message> for MessageInfo {
+    fn from(message: Message) -> Self {
+        Self {
+            id: message.id,
+            channel_id: message.channel_id,
+            author_id: message.author.id,
+            content: message.content,
+            timestamp: message.timestamp.unix_timestamp(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Embed {
+    pub title: String,
+    pub author: Option<String>,
+    pub description: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub color: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutgoingMessage {
+    pub content: Option<String>,
+    pub embed: Option<Embed>,
+    /// Message this one is sent as a reply to.
+    pub reply_to: Option<(ChannelId, MessageId)>,
+    /// The only users the message may ping, if set. Roles and @everyone are never pinged
+    /// then either.
+    pub allowed_users: Option<Vec<UserId>>,
+}
+
+impl OutgoingMessage {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            content: Some(content.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn embed(embed: Embed) -> Self {
+        Self {
+            embed: Some(embed),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MemberPresence {
+    pub user_id: UserId,
+    pub bot: bool,
+    pub activities: Vec<Activity>,
+}
+
+/// Lists channels and reads their history.
+#[async_trait]
+pub trait ChannelSource: Send + Sync {
+    /// Top level channels of a guild. Threads are not included.
+    async fn guild_channels(&self, guild_id: GuildId) -> Result<Vec<ChannelInfo>>;
+    /// Active threads (including forum posts) of a guild.
+    async fn active_threads(&self, guild_id: GuildId) -> Result<Vec<ChannelInfo>>;
+    /// The most recently archived public threads under a channel.
+    async fn archived_threads(&self, channel_id: ChannelId, limit: u64)
+        -> Result<Vec<ChannelInfo>>;
+    /// Up to `limit` messages sent before `before` (or the latest if `None`), newest first.
+    async fn messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: u64,
+    ) -> Result<Vec<MessageInfo>>;
+}
+
+/// Sends messages to channels.
+#[async_trait]
+pub trait MessageSink: Send + Sync {
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: &OutgoingMessage,
+    ) -> Result<MessageId>;
+    /// Whether messages in the channel can be crossposted to the servers following it.
+    async fn is_announcement_channel(&self, channel_id: ChannelId) -> Result<bool>;
+    /// Publishes a message in an announcement channel to the servers following it.
+    async fn crosspost(&self, channel_id: ChannelId, message_id: MessageId) -> Result<()>;
+}
+
+/// What members of the bot's guilds are currently doing.
+#[async_trait]
+pub trait PresenceSource: Send + Sync {
+    fn guilds(&self) -> Vec<GuildId>;
+    fn presences(&self, guild_id: GuildId) -> Vec<MemberPresence>;
+    /// The voice channel each member in one is connected to.
+    fn voice_channels(&self, guild_id: GuildId) -> HashMap<UserId, ChannelId>;
+    /// Fills in what's missing about members of `guilds` with presences, before they're read
+    /// for a ping.
+    async fn resolve_members(&self, _guilds: &[GuildId]) {}
+}
+
+/// Production implementation of the Discord boundaries over serenity's cache and HTTP client.
+#[derive(Clone)]
+pub struct SerenityDiscord {
+    cache: Arc<Cache>,
+    http: Arc<Http>,
+    /// Users the cache didn't have, as looked up over HTTP.
+    lookups: Arc<std::sync::Mutex<UserLookups>>,
+}
+
+impl SerenityDiscord {
+    pub fn new(cache: Arc<Cache>, http: Arc<Http>) -> Self {
+        Self {
+            cache,
+            http,
+            lookups: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChannelSource for SerenityDiscord {
+    async fn guild_channels(&self, guild_id: GuildId) -> Result<Vec<ChannelInfo>> {
+        let channels = guild_id.channels(&self.http).await?;
+        Ok(channels.into_values().map(ChannelInfo::from).collect())
+    }
+
+    async fn active_threads(&self, guild_id: GuildId) -> Result<Vec<ChannelInfo>> {
+        let threads = guild_id.get_active_threads(&self.http).await?;
+        Ok(threads.threads.into_iter().map(ChannelInfo::from).collect())
+    }
+
+    async fn archived_threads(
+        &self,
+        channel_id: ChannelId,
+        limit: u64,
+    ) -> Result<Vec<ChannelInfo>> {
+        let threads = channel_id
+            .get_archived_public_threads(&self.http, None, Some(limit))
+            .await?;
+        Ok(threads.threads.into_iter().map(ChannelInfo::from).collect())
+    }
+
+    async fn messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: u64,
+    ) -> Result<Vec<MessageInfo>> {
+        let messages = channel_id
+            .messages(&self.http, |retriever| match before {
+                Some(before) => retriever.before(before).limit(limit),
+                None => retriever.limit(limit),
+            })
+            .await?;
+        Ok(messages.into_iter().map(MessageInfo::from).collect())
+    }
+}
+
+#[async_trait]
+impl MessageSink for SerenityDiscord {
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: &OutgoingMessage,
+    ) -> Result<MessageId> {
+        let sent = channel_id
+            .send_message(&self.http, |m| {
+                if let Some(content) = &message.content {
+                    m.content(content);
+                }
+                if let Some(embed) = &message.embed {
+                    m.embed(|e| {
+                        e.title(&embed.title);
+                        if let Some(author) = &embed.author {
+                            e.author(|a| a.name(author));
+                        }
+                        e.description(&embed.description);
+                        if let Some(timestamp) = embed.timestamp {
+                            e.timestamp(timestamp.to_rfc3339());
+                        }
+                        e.color(embed.color);
+                        e
+                    });
+                }
+                if let Some(reference) = message.reply_to {
+                    m.reference_message(reference);
+                }
+                if let Some(users) = &message.allowed_users {
+                    m.allowed_mentions(|mentions| mentions.empty_parse().users(users.clone()));
+                }
+                m
+            })
+            .await?;
+        Ok(sent.id)
+    }
+
+    async fn is_announcement_channel(&self, channel_id: ChannelId) -> Result<bool> {
+        let kind = match self.cache.guild_channel(channel_id) {
+            Some(channel) => channel.kind,
+            None => match channel_id.to_channel(self.http.as_ref()).await? {
+                Channel::Guild(channel) => channel.kind,
+                _ => return Ok(false),
+            },
+        };
+        Ok(kind == ChannelType::News)
+    }
+
+    async fn crosspost(&self, channel_id: ChannelId, message_id: MessageId) -> Result<()> {
+        channel_id.crosspost(&self.http, message_id).await?;
+        Ok(())
+    }
+}
+
+impl SerenityDiscord {
+    /// Whether a user with a presence in `guild` is a bot, from whatever the cache or earlier
+    /// HTTP lookups know about them.
+    fn is_bot(&self, guild: &Guild, presence: &Presence) -> Option<bool> {
+        self.cached_is_bot(guild, presence)
+            .or_else(|| self.lookups.lock().unwrap().is_bot(presence.user.id))
+    }
+
+    /// Whether a user with a presence in `guild` is a bot, from the cache alone.
+    fn cached_is_bot(&self, guild: &Guild, presence: &Presence) -> Option<bool> {
+        let user_id = presence.user.id;
+        self.cache
+            .user(user_id)
+            .map(|user| user.bot)
+            .or_else(|| guild.members.get(&user_id).map(|member| member.user.bot))
+            .or(presence.user.bot)
+    }
+}
+
+#[async_trait]
+impl PresenceSource for SerenityDiscord {
+    fn guilds(&self) -> Vec<GuildId> {
+        self.cache.guilds()
+    }
+
+    fn presences(&self, guild_id: GuildId) -> Vec<MemberPresence> {
+        let guild = match self.cache.guild(guild_id) {
+            Some(g) => g,
+            None => {
+                eprintln!(
+                    "Failed to get guild with id {} when checking presences.",
+                    guild_id.0
+                );
+                return vec![];
+            }
+        };
+
+        let mut unresolved = 0;
+        let presences: Vec<MemberPresence> = guild
+            .presences
+            .values()
+            .filter_map(|presence| match self.is_bot(&guild, presence) {
+                Some(bot) => Some(MemberPresence {
+                    user_id: presence.user.id,
+                    bot,
+                    activities: presence.activities.clone(),
+                }),
+                None => {
+                    unresolved += 1;
+                    None
+                }
+            })
+            .collect();
+        if unresolved > 0 {
+            eprintln!(
+                "{}; {} users with presences not resolved yet",
+                MemberCoverage::of(&guild),
+                unresolved
+            );
+        }
+        presences
+    }
+
+    fn voice_channels(&self, guild_id: GuildId) -> HashMap<UserId, ChannelId> {
+        match self.cache.guild(guild_id) {
+            Some(guild) => guild
+                .voice_states
+                .into_iter()
+                .filter_map(|(user_id, state)| state.channel_id.map(|channel| (user_id, channel)))
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Looks up users with presences the cache can't place over HTTP, at most
+    /// [`MAX_USER_LOOKUPS`] at a time. Users whose lookup failed recently are skipped.
+    async fn resolve_members(&self, guilds: &[GuildId]) {
+        let mut presences = vec![];
+        for guild_id in guilds {
+            let guild = match self.cache.guild(guild_id) {
+                Some(guild) => guild,
+                None => continue,
+            };
+            presences.extend(
+                guild
+                    .presences
+                    .values()
+                    .map(|presence| (presence.user.id, self.cached_is_bot(&guild, presence))),
+            );
+        }
+        let missing = users_to_look_up(&presences, &self.lookups.lock().unwrap(), Instant::now());
+        if missing.is_empty() {
+            return;
+        }
+
+        let mut resolved = 0;
+        for user_id in missing.iter().take(MAX_USER_LOOKUPS) {
+            let bot = match self.http.get_user(user_id.0).await {
+                Ok(user) => {
+                    resolved += 1;
+                    Some(user.bot)
+                }
+                Err(e) => {
+                    eprintln!("Failed to fetch user {}: {}", user_id, e);
+                    None
+                }
+            };
+            self.lookups
+                .lock()
+                .unwrap()
+                .record(*user_id, bot, Instant::now());
+        }
+        println!(
+            "Resolved {} of {} users missing from the cache over HTTP",
+            resolved,
+            missing.len()
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use regex::Regex;
+use serenity::model::id::{ChannelId, RoleId};
+
+use crate::news::{Article, PatchSize};
+
+/// Which news items a guild wants announced and which channel they go to. Every given
+/// condition must match; an empty condition matches anything.
+#[derive(Clone, Debug)]
+pub struct FeedRule {
+    pub channel_id: ChannelId,
+    /// Matches items with any of these tags, e.g. `patchnotes`.
+    pub tags: Vec<String>,
+    /// Matches items from any of these feeds, e.g. `steam_community_announcements`.
+    pub feeds: Vec<String>,
+    pub title: Option<Regex>,
+    /// Whether matching items also ping players to restart their game.
+    pub restart_ping: bool,
+}
+
+impl FeedRule {
+    /// The rule used for guilds without any routes configured: patch notes, with a restart ping.
+    pub fn patch_notes(channel_id: ChannelId) -> Self {
+        Self {
+            channel_id,
+            tags: vec![String::from("patchnotes")],
+            feeds: vec![],
+            title: None,
+            restart_ping: true,
+        }
+    }
+
+    pub fn matches(&self, article: &Article) -> bool {
+        (self.tags.is_empty() || self.tags.iter().any(|tag| article.has_tag(tag)))
+            && (self.feeds.is_empty() || self.feeds.contains(&article.feedname))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| title.is_match(&article.title))
+    }
+}
+
+/// A role mentioned in a guild's patch announcements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnnounceRole {
+    pub role_id: RoleId,
+    /// Whether minor patches mention the role too, not just new gameplay versions.
+    pub minor: bool,
+}
+
+impl AnnounceRole {
+    pub fn wants(&self, size: PatchSize) -> bool {
+        size == PatchSize::Major || self.minor
+    }
+}
+
+/// Restart notices a guild wants posted in the text chat of the voice channels its players
+/// are sitting in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VoiceNotice {
+    /// Whether players in voice are only told there, rather than also in the ping channel.
+    pub replace_ping: bool,
+}
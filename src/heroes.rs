@@ -0,0 +1,264 @@
+/// Every hero, as patch notes name them.
+pub const HEROES: &[&str] = &[
+    "Abaddon",
+    "Alchemist",
+    "Ancient Apparition",
+    "Anti-Mage",
+    "Arc Warden",
+    "Axe",
+    "Bane",
+    "Batrider",
+    "Beastmaster",
+    "Bloodseeker",
+    "Bounty Hunter",
+    "Brewmaster",
+    "Bristleback",
+    "Broodmother",
+    "Centaur Warrunner",
+    "Chaos Knight",
+    "Chen",
+    "Clinkz",
+    "Clockwerk",
+    "Crystal Maiden",
+    "Dark Seer",
+    "Dark Willow",
+    "Dawnbreaker",
+    "Dazzle",
+    "Death Prophet",
+    "Disruptor",
+    "Doom",
+    "Dragon Knight",
+    "Drow Ranger",
+    "Earth Spirit",
+    "Earthshaker",
+    "Elder Titan",
+    "Ember Spirit",
+    "Enchantress",
+    "Enigma",
+    "Faceless Void",
+    "Grimstroke",
+    "Gyrocopter",
+    "Hoodwink",
+    "Huskar",
+    "Invoker",
+    "Io",
+    "Jakiro",
+    "Juggernaut",
+    "Keeper of the Light",
+    "Kez",
+    "Kunkka",
+    "Legion Commander",
+    "Leshrac",
+    "Lich",
+    "Lifestealer",
+    "Lina",
+    "Lion",
+    "Lone Druid",
+    "Luna",
+    "Lycan",
+    "Magnus",
+    "Marci",
+    "Mars",
+    "Medusa",
+    "Meepo",
+    "Mirana",
+    "Monkey King",
+    "Morphling",
+    "Muerta",
+    "Naga Siren",
+    "Nature's Prophet",
+    "Necrophos",
+    "Night Stalker",
+    "Nyx Assassin",
+    "Ogre Magi",
+    "Omniknight",
+    "Oracle",
+    "Outworld Destroyer",
+    "Pangolier",
+    "Phantom Assassin",
+    "Phantom Lancer",
+    "Phoenix",
+    "Primal Beast",
+    "Puck",
+    "Pudge",
+    "Pugna",
+    "Queen of Pain",
+    "Razor",
+    "Riki",
+    "Ringmaster",
+    "Rubick",
+    "Sand King",
+    "Shadow Demon",
+    "Shadow Fiend",
+    "Shadow Shaman",
+    "Silencer",
+    "Skywrath Mage",
+    "Slardar",
+    "Slark",
+    "Snapfire",
+    "Sniper",
+    "Spectre",
+    "Spirit Breaker",
+    "Storm Spirit",
+    "Sven",
+    "Techies",
+    "Templar Assassin",
+    "Terrorblade",
+    "Tidehunter",
+    "Timbersaw",
+    "Tinker",
+    "Tiny",
+    "Treant Protector",
+    "Troll Warlord",
+    "Tusk",
+    "Underlord",
+    "Undying",
+    "Ursa",
+    "Vengeful Spirit",
+    "Venomancer",
+    "Viper",
+    "Visage",
+    "Void Spirit",
+    "Warlock",
+    "Weaver",
+    "Windranger",
+    "Winter Wyvern",
+    "Witch Doctor",
+    "Wraith King",
+    "Zeus",
+];
+
+/// Other names patch notes have used for heroes, and who they mean.
+const HERO_ALIASES: &[(&str, &str)] = &[
+    ("Outworld Devourer", "Outworld Destroyer"),
+    ("Windrunner", "Windranger"),
+];
+
+/// Items patch notes commonly change.
+pub const ITEMS: &[&str] = &[
+    "Abyssal Blade",
+    "Aeon Disk",
+    "Aether Lens",
+    "Aghanim's Scepter",
+    "Aghanim's Shard",
+    "Arcane Boots",
+    "Armlet of Mordiggian",
+    "Assault Cuirass",
+    "Battle Fury",
+    "Black King Bar",
+    "Blade Mail",
+    "Blink Dagger",
+    "Bloodstone",
+    "Bloodthorn",
+    "Boots of Bearing",
+    "Boots of Travel",
+    "Bottle",
+    "Bracer",
+    "Butterfly",
+    "Crimson Guard",
+    "Dagon",
+    "Daedalus",
+    "Desolator",
+    "Diffusal Blade",
+    "Disperser",
+    "Divine Rapier",
+    "Drum of Endurance",
+    "Eternal Shroud",
+    "Ethereal Blade",
+    "Eul's Scepter of Divinity",
+    "Eye of Skadi",
+    "Force Staff",
+    "Gleipnir",
+    "Glimmer Cape",
+    "Guardian Greaves",
+    "Hand of Midas",
+    "Harpoon",
+    "Heart of Tarrasque",
+    "Heaven's Halberd",
+    "Helm of the Dominator",
+    "Helm of the Overlord",
+    "Holy Locket",
+    "Hurricane Pike",
+    "Kaya",
+    "Khanda",
+    "Linken's Sphere",
+    "Lotus Orb",
+    "Maelstrom",
+    "Manta Style",
+    "Mask of Madness",
+    "Medallion of Courage",
+    "Mekansm",
+    "Mjollnir",
+    "Monkey King Bar",
+    "Moon Shard",
+    "Nullifier",
+    "Octarine Core",
+    "Orchid Malevolence",
+    "Overwhelming Blink",
+    "Parasma",
+    "Pavise",
+    "Phase Boots",
+    "Phylactery",
+    "Pipe of Insight",
+    "Power Treads",
+    "Radiance",
+    "Refresher Orb",
+    "Revenant's Brooch",
+    "Rod of Atos",
+    "Sange",
+    "Sange and Yasha",
+    "Satanic",
+    "Scythe of Vyse",
+    "Shadow Blade",
+    "Shiva's Guard",
+    "Silver Edge",
+    "Solar Crest",
+    "Spirit Vessel",
+    "Swift Blink",
+    "Tranquil Boots",
+    "Urn of Shadows",
+    "Vanguard",
+    "Veil of Discord",
+    "Vladmir's Offering",
+    "Wind Waker",
+    "Witch Blade",
+    "Wraith Band",
+    "Yasha",
+    "Yasha and Kaya",
+    "Kaya and Sange",
+];
+
+/// `name` without case, spaces or punctuation, so "anti mage" and "Anti-Mage" compare equal.
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The hero `name` refers to, ignoring case and punctuation.
+pub fn find_hero(name: &str) -> Option<&'static str> {
+    let name = normalize(name);
+    if name.is_empty() {
+        return None;
+    }
+    HEROES
+        .iter()
+        .copied()
+        .find(|hero| normalize(hero) == name)
+        .or_else(|| {
+            HERO_ALIASES
+                .iter()
+                .find(|(alias, _)| normalize(alias) == name)
+                .map(|(_, hero)| *hero)
+        })
+}
+
+/// The item `name` refers to, ignoring case and punctuation.
+pub fn find_item(name: &str) -> Option<&'static str> {
+    let name = normalize(name);
+    if name.is_empty() {
+        return None;
+    }
+    ITEMS.iter().copied().find(|item| normalize(item) == name)
+}
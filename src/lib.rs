@@ -0,0 +1,39 @@
+use lazy_static::lazy_static;
+use serenity::model::id::GuildId;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+
+pub mod archive;
+pub mod builds;
+pub mod check_updates;
+pub mod commands;
+pub mod config;
+pub mod diff;
+pub mod discord;
+pub mod feed_routes;
+pub mod heroes;
+pub mod members;
+pub mod mentions;
+pub mod news;
+pub mod party;
+pub mod patch_changes;
+pub mod ping_cooldown;
+pub mod ping_history;
+pub mod ping_scanner;
+pub mod playing;
+pub mod poll_schedule;
+pub mod storage;
+pub mod subscriptions;
+pub mod user_prefs;
+pub mod webhooks;
+
+lazy_static! {
+    pub static ref READY: AtomicBool = AtomicBool::new(false);
+    pub static ref GUILD_DOTE_ROLES: HashMap<GuildId, u64> = [
+        (GuildId::from(434511133383065620u64), 1005581009569460305),
+        (GuildId::from(983098809733226577), 983217658575081522),
+    ]
+    .iter()
+    .map(|val| val.to_owned())
+    .collect();
+}
@@ -0,0 +1,149 @@
+use serenity::cache::Cache;
+use serenity::client::bridge::gateway::ChunkGuildFilter;
+use serenity::model::guild::Guild;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long a user that couldn't be looked up over HTTP is left before trying again.
+pub const FAILED_LOOKUP_RETRY: Duration = Duration::from_secs(60 * 60);
+/// Most looked up users remembered. The oldest lookups are forgotten first.
+pub const MAX_REMEMBERED_LOOKUPS: usize = 10_000;
+
+/// How much of a guild the cache knows about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemberCoverage {
+    pub guild_id: GuildId,
+    /// Members Discord says the guild has.
+    pub member_count: u64,
+    pub cached_members: usize,
+    pub presences: usize,
+}
+
+impl MemberCoverage {
+    pub fn of(guild: &Guild) -> Self {
+        Self {
+            guild_id: guild.id,
+            member_count: guild.member_count,
+            cached_members: guild.members.len(),
+            presences: guild.presences.len(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.cached_members as u64 >= self.member_count
+    }
+}
+
+impl fmt::Display for MemberCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = match self.member_count {
+            0 => 100,
+            count => (self.cached_members as u64 * 100 / count).min(100),
+        };
+        write!(
+            f,
+            "Guild {}: {}/{} members cached ({}%), {} presences",
+            self.guild_id, self.cached_members, self.member_count, percent, self.presences
+        )
+    }
+}
+
+/// Asks the gateway for the members of `guild` the cache is missing, so presences can be
+/// matched to users. Discord only sends large guilds' members on request.
+///
+/// Presences aren't requested with them: with the presence intent, every member who isn't
+/// offline (which anyone playing is) already has theirs in the guild's create event, and later
+/// changes arrive as presence updates. serenity drops any presences sent with member chunks
+/// anyway.
+pub fn request_missing_members(ctx: &Context, guild: &Guild) {
+    let coverage = MemberCoverage::of(guild);
+    println!("{}", coverage);
+    if !coverage.is_complete() {
+        ctx.shard
+            .chunk_guild(guild.id, None, ChunkGuildFilter::None, None);
+    }
+}
+
+/// Logs how much of a guild is cached once the last chunk of its members has arrived.
+pub fn log_chunk_coverage(cache: &Cache, guild_id: GuildId, chunk_index: u32, chunk_count: u32) {
+    if chunk_index + 1 < chunk_count {
+        return;
+    }
+    match cache.guild(guild_id) {
+        Some(guild) => println!("{}", MemberCoverage::of(&guild)),
+        None => eprintln!("Received members for uncached guild {}", guild_id),
+    }
+}
+
+/// Whether users the cache didn't have are bots, as looked up over HTTP, including lookups that
+/// failed so they aren't retried on every ping.
+#[derive(Debug, Default)]
+pub struct UserLookups {
+    /// Whether each user is a bot, or `None` if the lookup failed, with when it was made.
+    lookups: HashMap<UserId, (Option<bool>, Instant)>,
+}
+
+impl UserLookups {
+    /// Whether `user_id` is a bot, if a lookup found them.
+    pub fn is_bot(&self, user_id: UserId) -> Option<bool> {
+        self.lookups.get(&user_id).and_then(|(bot, _)| *bot)
+    }
+
+    /// Whether `user_id` should be looked up at `now`: they haven't been, or the last lookup
+    /// failed more than [`FAILED_LOOKUP_RETRY`] ago.
+    pub fn should_look_up(&self, user_id: UserId, now: Instant) -> bool {
+        match self.lookups.get(&user_id) {
+            Some((Some(_), _)) => false,
+            Some((None, at)) => now.duration_since(*at) >= FAILED_LOOKUP_RETRY,
+            None => true,
+        }
+    }
+
+    /// Remembers a lookup of `user_id`, `None` if it failed, forgetting the oldest ones past
+    /// [`MAX_REMEMBERED_LOOKUPS`].
+    pub fn record(&mut self, user_id: UserId, bot: Option<bool>, now: Instant) {
+        self.lookups.insert(user_id, (bot, now));
+        if self.lookups.len() > MAX_REMEMBERED_LOOKUPS {
+            let mut by_age: Vec<(UserId, Instant)> = self
+                .lookups
+                .iter()
+                .map(|(user_id, (_, at))| (*user_id, *at))
+                .collect();
+            by_age.sort_by_key(|(_, at)| *at);
+            let excess = self.lookups.len() - MAX_REMEMBERED_LOOKUPS;
+            for (user_id, _) in by_age.into_iter().take(excess) {
+                self.lookups.remove(&user_id);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lookups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lookups.is_empty()
+    }
+}
+
+/// Users among `presences` to look up over HTTP at `now`, sorted. Each presence is paired with
+/// whether the cache knows them to be a bot; those it doesn't, and no earlier lookup found, are
+/// looked up unless their last lookup failed too recently.
+pub fn users_to_look_up(
+    presences: &[(UserId, Option<bool>)],
+    lookups: &UserLookups,
+    now: Instant,
+) -> Vec<UserId> {
+    let mut missing: Vec<UserId> = presences
+        .iter()
+        .filter(|(user_id, cached)| cached.is_none() && lookups.is_bot(*user_id).is_none())
+        .map(|(user_id, _)| *user_id)
+        .filter(|user_id| lookups.should_look_up(*user_id, now))
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
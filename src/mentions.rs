@@ -0,0 +1,51 @@
+use serenity::model::id::UserId;
+
+use crate::discord::OutgoingMessage;
+
+/// Most characters Discord accepts in a message's content.
+pub const MESSAGE_LIMIT: usize = 2000;
+
+/// `items` as an English list: "a", "a and b", "a, b and c".
+pub fn join_list<S: AsRef<str>>(items: &[S]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.as_ref().to_string(),
+        [rest @ .., last] => {
+            let rest: Vec<&str> = rest.iter().map(AsRef::as_ref).collect();
+            format!("{} and {}", rest.join(", "), last.as_ref())
+        }
+    }
+}
+
+/// `users` as mentions in an English list.
+pub fn mention_list(users: &[UserId]) -> String {
+    let mentions: Vec<String> = users.iter().map(|user| format!("<@{}>", user)).collect();
+    join_list(&mentions)
+}
+
+/// Messages mentioning everyone in `entries`, split so each renders within `limit` characters.
+/// `render` builds a message's content from the entries it covers, and each message may only
+/// ping the users those entries belong to. An entry too long to fit even alone is sent as is.
+pub fn chunk_mentions<T>(
+    entries: &[T],
+    limit: usize,
+    user: impl Fn(&T) -> UserId,
+    render: impl Fn(&[T]) -> String,
+) -> Vec<OutgoingMessage> {
+    let message = |chunk: &[T]| OutgoingMessage {
+        allowed_users: Some(chunk.iter().map(&user).collect()),
+        ..OutgoingMessage::text(render(chunk))
+    };
+
+    let mut messages = vec![];
+    let mut start = 0;
+    while start < entries.len() {
+        let mut end = start + 1;
+        while end < entries.len() && render(&entries[start..=end]).chars().count() <= limit {
+            end += 1;
+        }
+        messages.push(message(&entries[start..end]));
+        start = end;
+    }
+    messages
+}
@@ -0,0 +1,297 @@
+use chrono::{DateTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::{
+    HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::StatusCode;
+use serde_json::Value;
+use serenity::async_trait;
+use serenity::futures::lock::Mutex;
+use std::fmt;
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// Steam's app ID for Dota 2.
+pub const DOTA_APP_ID: u32 = 570;
+
+lazy_static! {
+    /// A gameplay version like `7.36`, optionally with a letter for follow-up patches (`7.36a`).
+    static ref VERSION: Regex = Regex::new(r"\b\d+\.\d+([a-z]?)\b").unwrap();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchSize {
+    /// A new gameplay version, e.g. 7.36.
+    Major,
+    /// A lettered follow-up (7.36a) or anything without a version.
+    Minor,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Article {
+    pub gid: String,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+    /// The feed the item came from, e.g. `steam_community_announcements`.
+    pub feedname: String,
+    /// The body of the post, cut to the configured `news_maxlength`.
+    pub contents: String,
+    pub date: DateTime<Utc>,
+    pub tags: Vec<String>,
+}
+
+impl Article {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Whether these are notes for a new gameplay version, judging by the title.
+    pub fn patch_size(&self) -> PatchSize {
+        match VERSION.captures(&self.title) {
+            Some(captures) if captures[1].is_empty() => PatchSize::Major,
+            _ => PatchSize::Minor,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum NewsFetch {
+    Updated(Vec<Article>),
+    /// The feed hasn't changed since it was last fetched.
+    NotModified,
+}
+
+#[derive(Clone, Debug)]
+pub enum NewsError {
+    /// The server asked us to slow down, optionally saying for how long.
+    RateLimited(Option<Duration>),
+    /// Anything else, described for logging.
+    Failed(String),
+}
+
+impl fmt::Display for NewsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NewsError::RateLimited(Some(retry_after)) => write!(
+                f,
+                "Rate limited by Valve API, retry after {}s",
+                retry_after.as_secs()
+            ),
+            NewsError::RateLimited(None) => write!(f, "Rate limited by Valve API"),
+            NewsError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Somewhere news articles for the game can be fetched from.
+#[async_trait]
+pub trait NewsSource: Send + Sync {
+    /// Fetches the current news feed.
+    async fn fetch_news(&self) -> Result<NewsFetch, NewsError>;
+}
+
+/// The Steam Web API's `GetNewsForApp` feed.
+pub struct SteamNews {
+    client: reqwest::Client,
+    url: String,
+    validators: Mutex<CacheValidators>,
+}
+
+/// Response headers that let the next request be conditional, so an unchanged feed costs a
+/// 304 instead of a full download and parse.
+#[derive(Clone, Debug, Default)]
+struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+}
+
+impl SteamNews {
+    pub fn new(config: &Config) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .expect("Error creating HTTP client");
+        Self {
+            client,
+            url: news_url(config),
+            validators: Mutex::new(CacheValidators::default()),
+        }
+    }
+}
+
+pub fn news_url(config: &Config) -> String {
+    format!(
+        "{}/ISteamNews/GetNewsForApp/v0002/?appid={}&count={}&maxlength={}&format=json",
+        config.steam_api_url, DOTA_APP_ID, config.news_count, config.news_maxlength
+    )
+}
+
+#[async_trait]
+impl NewsSource for SteamNews {
+    async fn fetch_news(&self) -> Result<NewsFetch, NewsError> {
+        let validators = self.validators.lock().await.clone();
+        let mut request = self.client.get(&self.url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| NewsError::Failed(format!("Error checking for updates: {:?}", e)))?;
+        match response.status() {
+            StatusCode::NOT_MODIFIED => return Ok(NewsFetch::NotModified),
+            StatusCode::TOO_MANY_REQUESTS => {
+                // Only the delay-seconds form of Retry-After is used; dates fall back to backoff
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse().ok())
+                    .map(Duration::from_secs);
+                return Err(NewsError::RateLimited(retry_after));
+            }
+            status if !status.is_success() => {
+                return Err(NewsError::Failed(format!(
+                    "Valve API responded with status {}",
+                    status
+                )));
+            }
+            _ => (),
+        }
+
+        let new_validators = CacheValidators::from_headers(response.headers());
+        let body = response.text().await.map_err(|e| {
+            NewsError::Failed(format!(
+                "Failed to get body of response from reqwest: {:?}",
+                e
+            ))
+        })?;
+        let articles = parse_news(&body).map_err(NewsError::Failed)?;
+        // Only remember the validators once the body has been handled, so a bad response
+        // isn't skipped as "not modified" next time
+        *self.validators.lock().await = new_validators;
+        Ok(NewsFetch::Updated(articles))
+    }
+}
+
+/// Parses a `GetNewsForApp` response body. Malformed items are logged and skipped rather than
+/// failing the whole feed.
+pub fn parse_news(body: &str) -> Result<Vec<Article>, String> {
+    let json: Value = serde_json::from_str(body).map_err(|e| {
+        format!(
+            "Failed to convert response from Valve API with error: {:?}",
+            e
+        )
+    })?;
+
+    let appnews = json
+        .get("appnews")
+        .ok_or_else(|| String::from("No appnews found in response from Valve API"))?;
+
+    let newsitems = match appnews.get("newsitems") {
+        Some(Value::Array(newsitems)) => newsitems,
+        Some(v) => {
+            return Err(format!("Response from Valve API had property newsitems being something other than an array... {}", v));
+        }
+        None => {
+            return Err(String::from(
+                "No newsitems found in response from Valve API",
+            ))
+        }
+    };
+
+    let mut articles = vec![];
+    for item in newsitems {
+        let tags = match item.get("tags") {
+            Some(Value::Array(tags)) => tags
+                .iter()
+                .filter_map(|tag| match tag {
+                    Value::String(tag) => Some(tag.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            Some(v) => {
+                eprintln!("Response from Valve API had property tags being something other than an array... {}", v);
+                continue;
+            }
+            None => vec![],
+        };
+
+        let date = match item.get("date") {
+            Some(Value::Number(date)) => match date.as_i64() {
+                Some(date) => Utc.timestamp(date, 0),
+                None => {
+                    eprintln!("Failed to convert date for newsitem entry from Valve API to i64");
+                    continue;
+                }
+            },
+            Some(v) => {
+                eprintln!("newsitem entry from Valve API had property date being something other than a number... {}", v);
+                continue;
+            }
+            None => {
+                eprintln!("No date found for newsitem entry in response from Valve API");
+                continue;
+            }
+        };
+
+        macro_rules! get_string {
+            ($item: expr, $property: expr) => {
+                match $item.get($property) {
+                    Some(Value::String(string)) => string.to_string(),
+                    Some(v) => {
+                        eprintln!(
+                            "newsitem entry from Valve API had property {} being something other than a string... {}",
+                            $property, v
+                        );
+                        continue;
+                    }
+                    None => {
+                        eprintln!("No {} found for newsitem entry in response from Valve API", $property);
+                        continue;
+                    }
+                }
+            };
+        }
+
+        articles.push(Article {
+            gid: get_string!(item, "gid"),
+            title: get_string!(item, "title"),
+            author: get_string!(item, "author"),
+            url: get_string!(item, "url"),
+            feedname: match item.get("feedname") {
+                Some(Value::String(feedname)) => feedname.to_string(),
+                _ => String::new(),
+            },
+            contents: match item.get("contents") {
+                Some(Value::String(contents)) => contents.to_string(),
+                _ => String::new(),
+            },
+            date,
+            tags,
+        });
+    }
+    Ok(articles)
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serenity::model::id::UserId;
+
+use crate::mentions::mention_list;
+use crate::user_prefs::UserPrefs;
+
+/// Players in a full party.
+pub const PARTY_SIZE: usize = 5;
+
+/// A `/lfg` call gathering players for a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Party {
+    pub host: UserId,
+    /// When the host wants to start, or `None` for right away.
+    pub starts_at: Option<DateTime<Utc>>,
+    /// Players wanted, including the host. Closes once this many have joined.
+    pub slots: usize,
+    /// Everyone in, host first.
+    pub joined: Vec<UserId>,
+    pub maybe: Vec<UserId>,
+}
+
+impl Party {
+    /// A party with only the host in it. `slots` is clamped to 2..=[`PARTY_SIZE`].
+    pub fn new(host: UserId, slots: usize, starts_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            host,
+            starts_at,
+            slots: slots.clamp(2, PARTY_SIZE),
+            joined: vec![host],
+            maybe: vec![],
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.joined.len() >= self.slots
+    }
+
+    /// Adds `user_id` to the party, returning whether anything changed. Nobody can join a
+    /// full party.
+    pub fn join(&mut self, user_id: UserId) -> bool {
+        if self.is_full() || self.joined.contains(&user_id) {
+            return false;
+        }
+        self.maybe.retain(|user| *user != user_id);
+        self.joined.push(user_id);
+        true
+    }
+
+    /// Marks `user_id` as a maybe, taking them out of the party if they'd joined.
+    pub fn maybe(&mut self, user_id: UserId) -> bool {
+        if self.maybe.contains(&user_id) {
+            return false;
+        }
+        self.joined.retain(|user| *user != user_id);
+        self.maybe.push(user_id);
+        true
+    }
+
+    /// Removes `user_id` from both the party and the maybes.
+    pub fn leave(&mut self, user_id: UserId) -> bool {
+        let before = self.joined.len() + self.maybe.len();
+        self.joined.retain(|user| *user != user_id);
+        self.maybe.retain(|user| *user != user_id);
+        before != self.joined.len() + self.maybe.len()
+    }
+
+    /// The party's embed text: when it starts and who's in.
+    pub fn describe(&self) -> String {
+        let start = match self.starts_at {
+            Some(at) => format!("<t:{0}:t> (<t:{0}:R>)", at.timestamp()),
+            None => String::from("Now"),
+        };
+        let mut lines = vec![
+            format!("Starting: {}", start),
+            format!(
+                "Players ({}/{}): {}",
+                self.joined.len(),
+                self.slots,
+                mention_list(&self.joined)
+            ),
+        ];
+        if !self.maybe.is_empty() {
+            lines.push(format!("Maybe: {}", mention_list(&self.maybe)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Parses when a party starts: "now", or a HH:MM time in the host's timezone, which is the
+/// next time the clock shows it.
+pub fn parse_start(
+    s: &str,
+    prefs: &UserPrefs,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("now") {
+        return Ok(None);
+    }
+    let time = NaiveTime::parse_from_str(s, "%H:%M")
+        .map_err(|_| format!("{:?} should be a HH:MM time or \"now\"", s))?;
+    let local = prefs.timezone().local(now);
+    let mut start = local.date().and_time(time);
+    if start < local {
+        start += Duration::days(1);
+    }
+    Ok(Some(prefs.timezone().to_utc(start)))
+}
@@ -0,0 +1,152 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::heroes::{find_hero, find_item, normalize};
+
+lazy_static! {
+    /// BBCode and HTML tags, as Steam posts are marked up with either.
+    static ref MARKUP: Regex = Regex::new(r"\[/?[a-zA-Z0-9]+(=[^\]]*)?\]|<[^>]+>").unwrap();
+}
+
+/// What a patch changed for one hero or item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityChanges {
+    pub name: &'static str,
+    pub changes: Vec<String>,
+}
+
+/// The hero and item changes listed in a patch's notes, in the order they appear.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchChanges {
+    pub heroes: Vec<EntityChanges>,
+    pub items: Vec<EntityChanges>,
+}
+
+impl PatchChanges {
+    pub fn is_empty(&self) -> bool {
+        self.heroes.is_empty() && self.items.is_empty()
+    }
+
+    pub fn hero(&self, name: &str) -> Option<&EntityChanges> {
+        self.heroes.iter().find(|hero| hero.name == name)
+    }
+
+    pub fn item(&self, name: &str) -> Option<&EntityChanges> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    /// Names of every hero the patch changed.
+    pub fn hero_names(&self) -> Vec<&'static str> {
+        self.heroes.iter().map(|hero| hero.name).collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Heroes,
+    Items,
+    Other,
+}
+
+#[derive(Clone, Copy)]
+enum Entity {
+    Hero(&'static str),
+    Item(&'static str),
+}
+
+/// Picks the hero and item changes out of patch notes. A line naming a hero or item starts
+/// its changes, which run until the next name or section heading; "Name: change" lines count
+/// too. Under an "Items" heading names are looked up as items first, otherwise as heroes.
+pub fn extract_changes(contents: &str) -> PatchChanges {
+    let contents = contents.replace("[*]", "\n* ");
+    let text = MARKUP.replace_all(&contents, "");
+    let mut changes = PatchChanges::default();
+    let mut section = Section::Other;
+    let mut current: Option<Entity> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (bullet, line) = match line.strip_prefix(['*', '-', '•']) {
+            Some(rest) => (true, rest.trim()),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            continue;
+        }
+
+        if !bullet {
+            if let Some(heading) = heading(line) {
+                section = heading;
+                current = None;
+                continue;
+            }
+        }
+
+        let (name, rest) = match line.split_once(':') {
+            Some((name, rest)) => (name, Some(rest.trim())),
+            None => (line, None),
+        };
+        if let Some(entity) = lookup(name, section) {
+            current = Some(entity);
+            if let Some(rest) = rest.filter(|rest| !rest.is_empty()) {
+                changes.push(entity, rest);
+            } else {
+                changes.touch(entity);
+            }
+            continue;
+        }
+
+        if let Some(entity) = current {
+            changes.push(entity, line);
+        }
+    }
+    changes
+}
+
+impl PatchChanges {
+    fn entry(&mut self, entity: Entity) -> &mut EntityChanges {
+        let (list, name) = match entity {
+            Entity::Hero(name) => (&mut self.heroes, name),
+            Entity::Item(name) => (&mut self.items, name),
+        };
+        let index = match list.iter().position(|entry| entry.name == name) {
+            Some(index) => index,
+            None => {
+                list.push(EntityChanges {
+                    name,
+                    changes: vec![],
+                });
+                list.len() - 1
+            }
+        };
+        &mut list[index]
+    }
+
+    fn touch(&mut self, entity: Entity) {
+        self.entry(entity);
+    }
+
+    fn push(&mut self, entity: Entity, change: &str) {
+        self.entry(entity).changes.push(change.to_string());
+    }
+}
+
+fn heading(line: &str) -> Option<Section> {
+    match normalize(line).as_str() {
+        "heroes" | "heroupdates" | "herochanges" => Some(Section::Heroes),
+        "items" | "itemupdates" | "itemchanges" | "neutralitems" => Some(Section::Items),
+        "general" | "generalupdates" | "generalchanges" | "gameplay" | "gameplayupdates" => {
+            Some(Section::Other)
+        }
+        _ => None,
+    }
+}
+
+fn lookup(name: &str, section: Section) -> Option<Entity> {
+    let hero = || find_hero(name).map(Entity::Hero);
+    let item = || find_item(name).map(Entity::Item);
+    match section {
+        Section::Items => item().or_else(hero),
+        Section::Heroes | Section::Other => hero().or_else(item),
+    }
+}
@@ -0,0 +1,99 @@
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::prelude::*;
+use std::time::Duration;
+
+use crate::discord::{MessageInfo, MessageSink, OutgoingMessage, SerenityDiscord};
+use crate::ping_history::SharedPingHistory;
+
+/// Tells someone when @Dote was last pinged, with a link to that ping, and when it may be
+/// pinged again.
+pub fn cooldown_notice(guild_id: GuildId, last: &MessageInfo, cooldown: Duration) -> String {
+    format!(
+        "@Dote was already pinged <t:{}:R> ({}). It can be pinged again <t:{}:R>.",
+        last.timestamp,
+        last.id.link(last.channel_id, Some(guild_id)),
+        last.timestamp + cooldown.as_secs() as i64
+    )
+}
+
+/// Records @Dote pings as members send them. One sent within the guild's cooldown of the
+/// last is deleted and its author DMed why if the bot can manage messages in the channel,
+/// and replied to otherwise.
+pub async fn check_ping(ctx: &Context, message: &Message, history: &SharedPingHistory) {
+    let guild_id = match message.guild_id {
+        Some(guild_id) if !message.author.bot => guild_id,
+        _ => return,
+    };
+    match crate::GUILD_DOTE_ROLES.get(&guild_id) {
+        Some(role) if message.mention_roles.contains(&RoleId(*role)) => {}
+        _ => return,
+    }
+
+    let ping = MessageInfo::from(message.clone());
+    let mut history = history.lock().await;
+    let last = history.cooldown(guild_id).and_then(|cooldown| {
+        history
+            .within(guild_id, cooldown, ping.timestamp)
+            .map(|last| (last.clone(), cooldown))
+    });
+    let (last, cooldown) = match last {
+        Some(last) => last,
+        None => {
+            history.record(guild_id, ping);
+            return;
+        }
+    };
+    drop(history);
+
+    let notice = cooldown_notice(guild_id, &last, cooldown);
+    if can_manage_messages(ctx, message.channel_id) {
+        match message.delete(&ctx.http).await {
+            Ok(()) => {
+                let dm = format!(
+                    "Your message in <#{}> was removed. {}",
+                    message.channel_id, notice
+                );
+                if let Err(e) = message
+                    .author
+                    .direct_message(&ctx.http, |reply| reply.content(dm))
+                    .await
+                {
+                    eprintln!(
+                        "Error DMing {} about the ping cooldown: {:?}",
+                        message.author.id, e
+                    );
+                }
+                return;
+            }
+            Err(e) => eprintln!("Error deleting ping within cooldown: {:?}", e),
+        }
+    }
+
+    let discord = SerenityDiscord::new(ctx.cache.clone(), ctx.http.clone());
+    let reply = OutgoingMessage {
+        reply_to: Some((message.channel_id, message.id)),
+        allowed_users: Some(vec![]),
+        ..OutgoingMessage::text(notice)
+    };
+    if let Err(e) = discord.send_message(message.channel_id, &reply).await {
+        eprintln!("Error replying to ping within cooldown: {:?}", e);
+    }
+}
+
+fn can_manage_messages(ctx: &Context, channel_id: ChannelId) -> bool {
+    let channel = match ctx.cache.guild_channel(channel_id) {
+        Some(channel) => channel,
+        None => return false,
+    };
+    match channel.permissions_for_user(&ctx.cache, ctx.cache.current_user_id()) {
+        Ok(permissions) => permissions.manage_messages(),
+        Err(e) => {
+            eprintln!(
+                "Error getting permissions in channel {}: {:?}",
+                channel_id, e
+            );
+            false
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use serenity::futures::lock::Mutex;
+use serenity::model::id::GuildId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::discord::MessageInfo;
+
+/// The history as shared between commands and the event handler.
+pub type SharedPingHistory = Arc<Mutex<PingHistory>>;
+
+/// The newest @Dote ping the bot has seen or sent in each guild since it started, so recent
+/// pings are known without scanning channels.
+#[derive(Debug, Default)]
+pub struct PingHistory {
+    last: HashMap<GuildId, MessageInfo>,
+    cooldowns: HashMap<GuildId, Duration>,
+}
+
+impl PingHistory {
+    /// A history for guilds where members must wait `cooldowns` between pings.
+    pub fn new(cooldowns: HashMap<GuildId, Duration>) -> Self {
+        Self {
+            last: HashMap::new(),
+            cooldowns,
+        }
+    }
+
+    pub fn shared(self) -> SharedPingHistory {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Remembers `ping` unless a newer one in the guild is already known.
+    pub fn record(&mut self, guild_id: GuildId, ping: MessageInfo) {
+        match self.last.get(&guild_id) {
+            Some(last) if last.id >= ping.id => {}
+            _ => {
+                self.last.insert(guild_id, ping);
+            }
+        }
+    }
+
+    pub fn last(&self, guild_id: GuildId) -> Option<&MessageInfo> {
+        self.last.get(&guild_id)
+    }
+
+    /// The guild's configured cooldown, if it has one.
+    pub fn cooldown(&self, guild_id: GuildId) -> Option<Duration> {
+        self.cooldowns.get(&guild_id).copied()
+    }
+
+    /// The last ping in the guild if it was less than `cooldown` before `timestamp`.
+    pub fn within(
+        &self,
+        guild_id: GuildId,
+        cooldown: Duration,
+        timestamp: i64,
+    ) -> Option<&MessageInfo> {
+        self.last(guild_id)
+            .filter(|last| timestamp - last.timestamp < cooldown.as_secs() as i64)
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Utc};
+use serenity::futures::stream::{self, FuturesUnordered, StreamExt};
+use serenity::model::channel::ChannelType;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::discord::{ChannelInfo, ChannelSource, MessageInfo};
+
+/// Number of messages searched per channel when no depth is given.
+pub const DEFAULT_DEPTH: u64 = 100;
+/// Most messages Discord will return in one request.
+const PAGE_SIZE: u64 = 100;
+/// Archived threads are only scanned if they were archived within this many days.
+const ARCHIVED_THREAD_DAYS: i64 = 7;
+/// Number of archived threads requested per parent channel.
+const ARCHIVED_THREAD_LIMIT: u64 = 25;
+
+/// How far back each channel's history is searched.
+#[derive(Clone, Copy, Debug)]
+pub struct ScanDepth {
+    /// Maximum number of messages read per channel.
+    pub messages: u64,
+    /// Messages sent before this are not searched.
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl Default for ScanDepth {
+    fn default() -> Self {
+        Self {
+            messages: DEFAULT_DEPTH,
+            since: None,
+        }
+    }
+}
+
+/// Shared counters so callers can report how far along a scan is while it runs.
+#[derive(Default)]
+pub struct ScanProgress {
+    scanned: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl ScanProgress {
+    /// (scanned, total)
+    pub fn get(&self) -> (usize, usize) {
+        (
+            self.scanned.load(Ordering::Relaxed),
+            self.total.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Searches guild channels for the newest message matching a needle, keeping at most
+/// `concurrency` requests in flight so large guilds don't trip Discord's per-route rate limits.
+pub struct PingScanner {
+    concurrency: usize,
+    depth: ScanDepth,
+}
+
+impl PingScanner {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            depth: ScanDepth::default(),
+        }
+    }
+
+    pub fn depth(mut self, depth: ScanDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Every channel in the guild that may hold a ping, including active threads and forum
+    /// posts, which are not returned as part of the guild's channel list. Recently archived
+    /// public threads are included as well if `include_archived` is set.
+    pub async fn scannable_channels<S: ChannelSource + ?Sized>(
+        &self,
+        source: &S,
+        guild_id: GuildId,
+        include_archived: bool,
+        errors: &mut Vec<String>,
+    ) -> Option<Vec<ChannelInfo>> {
+        let mut channels = match source.guild_channels(guild_id).await {
+            Ok(channels) => channels,
+            Err(e) => {
+                let content = format!("Error getting channels: {:?}", e);
+                eprintln!("{}", content);
+                errors.push(content);
+                return None;
+            }
+        };
+
+        if include_archived {
+            let archived = self.archived_threads(source, guild_id, &channels).await;
+            channels.extend(archived);
+        }
+
+        match source.active_threads(guild_id).await {
+            Ok(threads) => channels.extend(threads),
+            Err(e) => {
+                let content = format!("Error getting active threads: {:?}", e);
+                eprintln!("{}", content);
+                errors.push(content);
+            }
+        }
+
+        Some(channels)
+    }
+
+    /// Public threads under `parents` that were archived within the last
+    /// [`ARCHIVED_THREAD_DAYS`].
+    async fn archived_threads<S: ChannelSource + ?Sized>(
+        &self,
+        source: &S,
+        guild_id: GuildId,
+        parents: &[ChannelInfo],
+    ) -> Vec<ChannelInfo> {
+        let cutoff = Utc::now().timestamp() - ARCHIVED_THREAD_DAYS * 60 * 60 * 24;
+        let parent_ids: Vec<ChannelId> = parents
+            .iter()
+            .filter(|channel| {
+                matches!(
+                    channel.kind,
+                    ChannelType::Text | ChannelType::News | ChannelType::Forum
+                )
+            })
+            .map(|channel| channel.id)
+            .collect();
+
+        let thread_lists: Vec<Vec<ChannelInfo>> = stream::iter(parent_ids)
+            .map(|channel_id| async move {
+                match source
+                    .archived_threads(channel_id, ARCHIVED_THREAD_LIMIT)
+                    .await
+                {
+                    Ok(threads) => threads,
+                    Err(e) => {
+                        // Missing permissions on a single channel shouldn't fail the whole scan
+                        eprintln!(
+                            "Error getting archived threads for channel {:?} in guild {:?} with error {:?}",
+                            channel_id, guild_id, e
+                        );
+                        vec![]
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        thread_lists
+            .into_iter()
+            .flatten()
+            .filter(|thread| {
+                thread
+                    .archived_at
+                    .is_some_and(|archived_at| archived_at >= cutoff)
+            })
+            .collect()
+    }
+
+    /// Finds the newest message across `channels` whose content contains `needle`.
+    ///
+    /// Channels are scanned in order of their most recent message. Since message IDs are
+    /// snowflakes (and so ordered by creation time), once a match is found that is newer than
+    /// the last message of every channel still waiting to be scanned, none of those channels can
+    /// hold anything newer and the scan stops early.
+    pub async fn find_newest<S: ChannelSource + ?Sized>(
+        &self,
+        source: &S,
+        channels: Vec<ChannelInfo>,
+        needle: &str,
+        progress: &ScanProgress,
+    ) -> Option<MessageInfo> {
+        // Sorted oldest first so the most recently active channel can be popped off the end.
+        // Channels without a last message have never been posted in, and channels whose last
+        // message is older than `since` have nothing in range, so both can be skipped.
+        let since = self.depth.since.map(|since| since.timestamp());
+        let mut pending: Vec<(ChannelId, MessageId)> = channels
+            .into_iter()
+            .filter(|channel| can_hold_messages(channel.kind))
+            .filter_map(|channel| channel.last_message_id.map(|last| (channel.id, last)))
+            .filter(|(_, last_message_id)| match since {
+                Some(since) => last_message_id.created_at().unix_timestamp() >= since,
+                None => true,
+            })
+            .collect();
+        pending.sort_by_key(|(_, last_message_id)| *last_message_id);
+        progress.total.store(pending.len(), Ordering::Relaxed);
+
+        let mut in_flight = FuturesUnordered::new();
+        let mut newest: Option<MessageInfo> = None;
+        loop {
+            while in_flight.len() < self.concurrency {
+                let (channel_id, last_message_id) = match pending.last() {
+                    Some(next) => *next,
+                    None => break,
+                };
+                if let Some(found) = &newest {
+                    if last_message_id < found.id {
+                        pending.clear();
+                        break;
+                    }
+                }
+                pending.pop();
+                in_flight.push(scan_channel(source, channel_id, needle, self.depth));
+            }
+
+            match in_flight.next().await {
+                Some(result) => {
+                    progress.scanned.fetch_add(1, Ordering::Relaxed);
+                    if let Some(message) = result {
+                        let is_newer = match &newest {
+                            Some(current) => message.id > current.id,
+                            None => true,
+                        };
+                        if is_newer {
+                            newest = Some(message);
+                        }
+                    }
+                }
+                None => break,
+            }
+        }
+        newest
+    }
+}
+
+/// Categories, directories and the like can't contain messages, so requesting them only
+/// wastes rate limit. Voice and stage channels have their own text chat.
+fn can_hold_messages(kind: ChannelType) -> bool {
+    matches!(
+        kind,
+        ChannelType::Text
+            | ChannelType::News
+            | ChannelType::Voice
+            | ChannelType::Stage
+            | ChannelType::NewsThread
+            | ChannelType::PublicThread
+            | ChannelType::PrivateThread
+    )
+}
+
+/// Pages backwards through a channel's history until a message containing `needle` is found,
+/// `depth` is exhausted, or the start of the channel is reached.
+async fn scan_channel<S: ChannelSource + ?Sized>(
+    source: &S,
+    channel_id: ChannelId,
+    needle: &str,
+    depth: ScanDepth,
+) -> Option<MessageInfo> {
+    let since = depth.since.map(|since| since.timestamp());
+    let mut before: Option<MessageId> = None;
+    let mut read = 0;
+    while read < depth.messages {
+        let limit = (depth.messages - read).min(PAGE_SIZE);
+        let messages = match source.messages(channel_id, before, limit).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                eprintln!(
+                    "Error getting messages for channel {:?} with error {:?}",
+                    channel_id, e
+                );
+                return None;
+            }
+        };
+        let page_len = messages.len() as u64;
+        read += page_len;
+
+        // Messages come back newest first, so the first match is the newest in the channel
+        for message in messages {
+            if since.is_some_and(|since| message.timestamp < since) {
+                return None;
+            }
+            if message.content.contains(needle) {
+                return Some(message);
+            }
+            before = Some(message.id);
+        }
+
+        // A short page means there's no older history left
+        if page_len < limit {
+            break;
+        }
+    }
+    None
+}
@@ -0,0 +1,101 @@
+use regex::Regex;
+use serenity::model::gateway::{Activity, ActivityType};
+use serenity::model::id::UserId;
+
+/// Discord's application id for Dota 2, reported with activities it detects.
+pub const DOTA_APPLICATION_ID: u64 = 356875988589740042;
+
+/// Names Discord shows for Dota 2 when it doesn't report the application id.
+pub const DOTA_NAME_PATTERN: &str = "(?i)^dota 2$";
+
+/// What a player is doing in the client, as far as their rich presence says.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    InMatch,
+    /// In a lobby or looking for a match.
+    InLobby,
+    Spectating,
+    MainMenu,
+    /// Running the client without saying more.
+    Unknown,
+}
+
+/// Someone running the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Player {
+    pub user_id: UserId,
+    pub status: GameStatus,
+}
+
+/// Recognizes the game among a member's activities. Only "Playing" activities count, so
+/// custom statuses and songs that mention the game don't.
+#[derive(Clone, Debug)]
+pub struct GameMatcher {
+    application_ids: Vec<u64>,
+    names: Vec<Regex>,
+}
+
+impl Default for GameMatcher {
+    /// Dota 2 by its application id or exact name.
+    fn default() -> Self {
+        Self {
+            application_ids: vec![DOTA_APPLICATION_ID],
+            names: vec![Regex::new(DOTA_NAME_PATTERN).unwrap()],
+        }
+    }
+}
+
+impl GameMatcher {
+    /// Matches activities from any of `application_ids` or with a name matching any of
+    /// `names`.
+    pub fn new(application_ids: Vec<u64>, names: Vec<Regex>) -> Self {
+        Self {
+            application_ids,
+            names,
+        }
+    }
+
+    pub fn matches(&self, activity: &Activity) -> bool {
+        activity.kind == ActivityType::Playing
+            && (activity
+                .application_id
+                .is_some_and(|id| self.application_ids.contains(&id.0))
+                || self.names.iter().any(|name| name.is_match(&activity.name)))
+    }
+
+    /// What the player is doing if any of `activities` is the game.
+    pub fn status(&self, activities: &[Activity]) -> Option<GameStatus> {
+        activities
+            .iter()
+            .find(|activity| self.matches(activity))
+            .map(rich_presence_status)
+    }
+}
+
+/// Reads the client's rich presence text, e.g. "Playing as Axe" or "Finding a Match".
+pub fn rich_presence_status(activity: &Activity) -> GameStatus {
+    let text = [&activity.details, &activity.state]
+        .into_iter()
+        .flatten()
+        .map(|text| text.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ");
+    let has = |phrases: &[&str]| phrases.iter().any(|phrase| text.contains(phrase));
+
+    if has(&["spectating", "watching"]) {
+        GameStatus::Spectating
+    } else if has(&["playing as", "playing a match", "in a match", "in match"]) {
+        GameStatus::InMatch
+    } else if has(&[
+        "lobby",
+        "finding a match",
+        "looking for a match",
+        "in queue",
+    ]) {
+        GameStatus::InLobby
+    } else if has(&["main menu"]) {
+        GameStatus::MainMenu
+    } else {
+        GameStatus::Unknown
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// A daily range of times, which may wrap past midnight (e.g. `22:00-02:00`). The patch
+/// window is in UTC; quiet hours are in the user's timezone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("{:?} should look like HH:MM-HH:MM", s))?;
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|e| format!("{:?} is not a HH:MM time ({})", time.trim(), e))
+        };
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Decides how long to wait between polls: faster inside the patch window, slower outside it,
+/// and backing off exponentially while requests keep failing.
+pub struct PollSchedule {
+    interval: Duration,
+    jitter: Duration,
+    patch_window: Option<TimeWindow>,
+    patch_window_interval: Duration,
+    max_backoff: Duration,
+    failures: u32,
+    retry_after: Option<Duration>,
+}
+
+impl PollSchedule {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            interval: config.poll_interval,
+            jitter: config.poll_jitter,
+            patch_window: config.patch_window,
+            patch_window_interval: config.patch_window_poll_interval,
+            max_backoff: config.max_backoff,
+            failures: 0,
+            retry_after: None,
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.retry_after = None;
+    }
+
+    /// Records a failed poll. `retry_after` is the server's requested wait, if it gave one.
+    pub fn record_failure(&mut self, retry_after: Option<Duration>) {
+        self.failures = self.failures.saturating_add(1);
+        self.retry_after = retry_after;
+    }
+
+    /// How long to wait before polling again, without jitter.
+    pub fn base_delay(&self, now: DateTime<Utc>) -> Duration {
+        let interval = match self.patch_window {
+            Some(window) if window.contains(now.time()) => self.patch_window_interval,
+            _ => self.interval,
+        };
+        if self.failures == 0 {
+            return interval;
+        }
+
+        // Double the wait for every consecutive failure, but never wait less than asked to
+        let backoff = interval
+            .checked_mul(2u32.saturating_pow(self.failures.min(16)))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        match self.retry_after {
+            Some(retry_after) => backoff.max(retry_after),
+            None => backoff,
+        }
+    }
+
+    /// How long to wait before polling again, with up to the configured jitter added.
+    pub fn next_delay(&self, now: DateTime<Utc>) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        let jitter = match jitter_ms {
+            0 => 0,
+            _ => rand::thread_rng().gen_range(0..=jitter_ms),
+        };
+        self.base_delay(now) + Duration::from_millis(jitter)
+    }
+}
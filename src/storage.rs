@@ -0,0 +1,26 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Reads the JSON saved at `path`, or the default if there's no file yet. `what` names the
+/// file in errors.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {} {}: {}", what, path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {} {}: {}", what, path.display(), e)),
+    }
+}
+
+/// Saves `value` as JSON at `path`. `what` names the file in errors.
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", what, e))?;
+    // Write then rename, so a crash mid-write can't leave a truncated file
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, json)
+        .and_then(|_| fs::rename(&temp, path))
+        .map_err(|e| format!("Failed to save {} {}: {}", what, path.display(), e))
+}
@@ -0,0 +1,129 @@
+use serenity::futures::lock::Mutex;
+use serenity::model::id::{GuildId, UserId};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::storage::{load_json, save_json};
+
+/// Heroes each user follows, by guild then user id.
+type HeroFollows = BTreeMap<u64, BTreeMap<u64, BTreeSet<String>>>;
+
+/// The subscriptions as shared between the update checker and commands.
+pub type SharedSubscriptions = Arc<Mutex<Subscriptions>>;
+
+/// Which heroes users want to hear about patches to, saved to a JSON file so they survive
+/// restarts.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    path: Option<PathBuf>,
+    heroes: HeroFollows,
+}
+
+impl Subscriptions {
+    /// Subscriptions that aren't saved anywhere.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn shared(self) -> SharedSubscriptions {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Loads the subscriptions saved at `path`, or starts with none there if it doesn't exist.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let heroes = load_json(&path, "subscriptions")?;
+        Ok(Self {
+            path: Some(path),
+            heroes,
+        })
+    }
+
+    /// Subscribes `user_id` to `hero` in `guild_id` and saves, returning whether they weren't
+    /// already.
+    pub fn subscribe(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        hero: &str,
+    ) -> Result<bool, String> {
+        let added = self
+            .heroes
+            .entry(guild_id.0)
+            .or_default()
+            .entry(user_id.0)
+            .or_default()
+            .insert(hero.to_string());
+        if added {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Unsubscribes `user_id` from `hero` in `guild_id` and saves, returning whether they were
+    /// subscribed.
+    pub fn unsubscribe(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        hero: &str,
+    ) -> Result<bool, String> {
+        let users = match self.heroes.get_mut(&guild_id.0) {
+            Some(users) => users,
+            None => return Ok(false),
+        };
+        let removed = match users.get_mut(&user_id.0) {
+            Some(heroes) => {
+                let removed = heroes.remove(hero);
+                if heroes.is_empty() {
+                    users.remove(&user_id.0);
+                }
+                removed
+            }
+            None => false,
+        };
+        if users.is_empty() {
+            self.heroes.remove(&guild_id.0);
+        }
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// The heroes `user_id` follows in `guild_id`, alphabetically.
+    pub fn heroes(&self, guild_id: GuildId, user_id: UserId) -> Vec<String> {
+        self.heroes
+            .get(&guild_id.0)
+            .and_then(|users| users.get(&user_id.0))
+            .map(|heroes| heroes.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Everyone in `guild_id` following any of `heroes`, with which of them they follow.
+    pub fn subscribers(&self, guild_id: GuildId, heroes: &[&str]) -> Vec<(UserId, Vec<String>)> {
+        let users = match self.heroes.get(&guild_id.0) {
+            Some(users) => users,
+            None => return vec![],
+        };
+        users
+            .iter()
+            .filter_map(|(user_id, followed)| {
+                let touched: Vec<String> = heroes
+                    .iter()
+                    .filter(|hero| followed.contains(**hero))
+                    .map(|hero| hero.to_string())
+                    .collect();
+                (!touched.is_empty()).then_some((UserId(*user_id), touched))
+            })
+            .collect()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        match &self.path {
+            Some(path) => save_json(path, &self.heroes, "subscriptions"),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serenity::futures::lock::Mutex;
+use serenity::model::id::UserId;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::poll_schedule::TimeWindow;
+use crate::storage::{load_json, save_json};
+
+/// Largest UTC offset accepted, in hours. Real ones range from -12 to +14.
+const MAX_OFFSET_HOURS: i32 = 14;
+
+/// A timezone from the tz database like "Europe/Berlin", whose offset follows daylight saving,
+/// or a fixed offset from UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timezone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// The offset from UTC in effect at `at`.
+    pub fn offset_at(&self, at: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Self::Named(tz) => tz.offset_from_utc_datetime(&at.naive_utc()).fix(),
+            Self::Fixed(offset) => *offset,
+        }
+    }
+
+    /// What the clock shows at `at`.
+    pub fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        at.with_timezone(&self.offset_at(at)).naive_local()
+    }
+
+    /// When the clock shows `local`. Of a time shown twice as daylight saving ends, the first is
+    /// taken, and a time skipped as it starts is taken as the one an hour later.
+    pub fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let tz = match self {
+            Self::Named(tz) => tz,
+            Self::Fixed(offset) => {
+                let utc = local - Duration::seconds(i64::from(offset.local_minus_utc()));
+                return DateTime::from_utc(utc, Utc);
+            }
+        };
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => at.with_timezone(&Utc),
+            LocalResult::None => self.to_utc(local + Duration::hours(1)),
+        }
+    }
+}
+
+impl Default for Timezone {
+    fn default() -> Self {
+        Self::Fixed(FixedOffset::east(0))
+    }
+}
+
+/// The zone's name, or its offset like "UTC+10:00".
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Named(tz) => f.write_str(tz.name()),
+            Self::Fixed(offset) => f.write_str(&format_utc_offset(*offset)),
+        }
+    }
+}
+
+/// Parses a tz database name like "Europe/Berlin", or failing that an offset from UTC.
+impl FromStr for Timezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(tz) = s.trim().parse::<Tz>() {
+            return Ok(Self::Named(tz));
+        }
+        parse_utc_offset(s).map(Self::Fixed).map_err(|_| {
+            format!(
+                "{:?} should be a timezone like Europe/Berlin, or an offset from UTC like UTC+10 or UTC-03:30",
+                s
+            )
+        })
+    }
+}
+
+/// How a user wants the bot to treat them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UserPrefs {
+    /// Their timezone. UTC if unset.
+    pub timezone: Option<Timezone>,
+    /// Local times they don't want to be pinged at.
+    pub quiet_hours: Option<TimeWindow>,
+}
+
+impl UserPrefs {
+    pub fn timezone(&self) -> Timezone {
+        self.timezone.unwrap_or_default()
+    }
+
+    /// When the quiet hours `now` falls in end, or `None` if it's not in them.
+    pub fn quiet_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let window = self.quiet_hours?;
+        let local = self.timezone().local(now);
+        if !window.contains(local.time()) {
+            return None;
+        }
+        let mut end = local.date().and_time(window.end);
+        if end <= local {
+            end += Duration::days(1);
+        }
+        Some(self.timezone().to_utc(end))
+    }
+
+    /// `at` as a time in the user's timezone, e.g. "2024-05-01 21:30 UTC+10:00" or
+    /// "2024-07-01 13:30 Europe/Berlin".
+    pub fn format_time(&self, at: DateTime<Utc>) -> String {
+        format!(
+            "{} {}",
+            self.timezone().local(at).format("%Y-%m-%d %H:%M"),
+            self.timezone()
+        )
+    }
+}
+
+/// Parses a timezone given as an offset from UTC: "UTC", "UTC+10", "GMT-3", "+05:30".
+pub fn parse_utc_offset(s: &str) -> Result<FixedOffset, String> {
+    let error = || {
+        format!(
+            "{:?} should be an offset from UTC like UTC+10 or UTC-03:30",
+            s
+        )
+    };
+    let trimmed = s.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    if rest.is_empty() || rest == "Z" {
+        return Ok(FixedOffset::east(0));
+    }
+
+    let (sign, rest) = if let Some(rest) = rest.strip_prefix('+') {
+        (1, rest)
+    } else if let Some(rest) = rest.strip_prefix('-') {
+        (-1, rest)
+    } else {
+        return Err(error());
+    };
+    // Users can type anything, so check it's ASCII before splitting at a byte offset
+    if !rest.is_ascii() {
+        return Err(error());
+    }
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| error())?;
+    let minutes: i32 = minutes.parse().map_err(|_| error())?;
+    if hours > MAX_OFFSET_HOURS || minutes >= 60 {
+        return Err(error());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(error)
+}
+
+/// `offset` as "UTC", "UTC+10:00" or "UTC-03:30".
+pub fn format_utc_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    if seconds == 0 {
+        return String::from("UTC");
+    }
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!(
+        "UTC{}{:02}:{:02}",
+        sign,
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+/// A user's preferences as they're saved.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct StoredPrefs {
+    /// Name of a tz database timezone. Fixed offsets are kept in `utc_offset_secs` instead.
+    timezone: Option<String>,
+    utc_offset_secs: Option<i32>,
+    quiet_hours: Option<String>,
+}
+
+impl From<UserPrefs> for StoredPrefs {
+    fn from(prefs: UserPrefs) -> Self {
+        let (timezone, utc_offset_secs) = match prefs.timezone {
+            Some(Timezone::Named(tz)) => (Some(tz.name().to_string()), None),
+            Some(Timezone::Fixed(offset)) => (None, Some(offset.local_minus_utc())),
+            None => (None, None),
+        };
+        Self {
+            timezone,
+            utc_offset_secs,
+            quiet_hours: prefs.quiet_hours.map(|window| window.to_string()),
+        }
+    }
+}
+
+impl From<&StoredPrefs> for UserPrefs {
+    fn from(stored: &StoredPrefs) -> Self {
+        Self {
+            timezone: match &stored.timezone {
+                Some(name) => name.parse().ok().map(Timezone::Named),
+                None => stored
+                    .utc_offset_secs
+                    .and_then(FixedOffset::east_opt)
+                    .map(Timezone::Fixed),
+            },
+            quiet_hours: stored
+                .quiet_hours
+                .as_deref()
+                .and_then(|window| window.parse().ok()),
+        }
+    }
+}
+
+/// The preferences as shared between the update checker and commands.
+pub type SharedPrefs = Arc<Mutex<PrefsStore>>;
+
+/// Every user's `/prefs`, saved to a JSON file so they survive restarts.
+#[derive(Debug, Default)]
+pub struct PrefsStore {
+    path: Option<PathBuf>,
+    users: BTreeMap<u64, StoredPrefs>,
+}
+
+impl PrefsStore {
+    /// Preferences that aren't saved anywhere.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn shared(self) -> SharedPrefs {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Loads the preferences saved at `path`, or starts with none there if it doesn't exist.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let users = load_json(&path, "user preferences")?;
+        Ok(Self {
+            path: Some(path),
+            users,
+        })
+    }
+
+    /// `user_id`'s preferences, or the defaults if they haven't set any.
+    pub fn get(&self, user_id: UserId) -> UserPrefs {
+        self.users
+            .get(&user_id.0)
+            .map(UserPrefs::from)
+            .unwrap_or_default()
+    }
+
+    /// Replaces `user_id`'s preferences and saves.
+    pub fn set(&mut self, user_id: UserId, prefs: UserPrefs) -> Result<(), String> {
+        if prefs == UserPrefs::default() {
+            self.users.remove(&user_id.0);
+        } else {
+            self.users.insert(user_id.0, StoredPrefs::from(prefs));
+        }
+        match &self.path {
+            Some(path) => save_json(path, &self.users, "user preferences"),
+            None => Ok(()),
+        }
+    }
+}
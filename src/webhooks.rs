@@ -0,0 +1,120 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use serenity::async_trait;
+use serenity::model::id::GuildId;
+
+use crate::discord::Embed;
+
+lazy_static! {
+    /// A markdown link, `[text](url)`.
+    static ref MARKDOWN_LINK: Regex = Regex::new(r"\[([^\]]*)\]\(([^)\s]*)\)").unwrap();
+}
+
+/// Somewhere outside the bot's guild channels that announcements are also sent.
+#[async_trait]
+pub trait AnnouncementSink: Send + Sync {
+    /// Sends an announcement. Errors are returned as a description to be logged.
+    async fn announce(&self, embed: &Embed) -> Result<(), String>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WebhookKind {
+    /// A Discord channel webhook, optionally posting under its own name and avatar.
+    Discord {
+        username: Option<String>,
+        avatar_url: Option<String>,
+    },
+    /// The announcement's fields as a flat JSON object.
+    Json,
+    /// Slack incoming webhooks, and Matrix bridges that accept the same `text` payload.
+    Slack,
+}
+
+/// An HTTP endpoint announcements are posted to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Webhook {
+    /// The guild whose announcements this receives, or `None` for all of them.
+    pub guild_id: Option<GuildId>,
+    pub url: String,
+    pub kind: WebhookKind,
+}
+
+impl Webhook {
+    /// The request body announcing `embed`.
+    pub fn payload(&self, embed: &Embed) -> Value {
+        match &self.kind {
+            WebhookKind::Discord {
+                username,
+                avatar_url,
+            } => {
+                let mut discord_embed = Map::new();
+                discord_embed.insert(String::from("title"), json!(embed.title));
+                discord_embed.insert(String::from("description"), json!(embed.description));
+                discord_embed.insert(String::from("color"), json!(embed.color));
+                if let Some(author) = &embed.author {
+                    discord_embed.insert(String::from("author"), json!({ "name": author }));
+                }
+                if let Some(timestamp) = embed.timestamp {
+                    discord_embed.insert(String::from("timestamp"), json!(timestamp.to_rfc3339()));
+                }
+
+                let mut payload = Map::new();
+                payload.insert(String::from("embeds"), json!([discord_embed]));
+                if let Some(username) = username {
+                    payload.insert(String::from("username"), json!(username));
+                }
+                if let Some(avatar_url) = avatar_url {
+                    payload.insert(String::from("avatar_url"), json!(avatar_url));
+                }
+                Value::Object(payload)
+            }
+            WebhookKind::Json => json!({
+                "title": embed.title,
+                "author": embed.author,
+                "description": embed.description,
+                "timestamp": embed.timestamp.map(|timestamp| timestamp.to_rfc3339()),
+            }),
+            WebhookKind::Slack => json!({
+                "text": format!(
+                    "*{}*\n{}",
+                    embed.title,
+                    MARKDOWN_LINK.replace_all(&embed.description, "<$2|$1>")
+                ),
+            }),
+        }
+    }
+}
+
+/// Posts announcements to a [`Webhook`].
+pub struct WebhookSink {
+    client: reqwest::Client,
+    webhook: Webhook,
+}
+
+impl WebhookSink {
+    pub fn new(client: reqwest::Client, webhook: Webhook) -> Self {
+        Self { client, webhook }
+    }
+}
+
+#[async_trait]
+impl AnnouncementSink for WebhookSink {
+    async fn announce(&self, embed: &Embed) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.webhook.url)
+            .json(&self.webhook.payload(embed))
+            .send()
+            .await
+            // Webhook URLs carry their secret, so keep them out of the logs
+            .map_err(|e| format!("Error posting to webhook: {:?}", e.without_url()))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Webhook responded with status {}",
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::{article, at, FakeDiscord, FakeNews};
+use jenkins_bot::archive::{contents_hash, ArchivedPatch, PatchArchive};
+use jenkins_bot::check_updates::UpdateChecker;
+use jenkins_bot::news::Article;
+use serenity::model::id::GuildId;
+
+fn patch(gid: &str, title: &str, date: i64, contents: &str) -> Article {
+    let mut article = article(gid, title, at(date), &["patchnotes"]);
+    article.contents = contents.to_string();
+    article
+}
+
+fn archive() -> PatchArchive {
+    let mut archive = PatchArchive::in_memory();
+    archive
+        .record(
+            &[
+                patch(
+                    "1",
+                    "7.33",
+                    1000,
+                    "New map, Tormentors and neutral item changes",
+                ),
+                patch("2", "7.33b", 2000, "Hero balance changes"),
+                patch("3", "7.33c", 3000, "Tormentor respawn time increased"),
+                patch("4", "Dota Plus Update", 4000, "New seasonal rewards"),
+            ],
+            0,
+        )
+        .unwrap();
+    archive
+}
+
+fn titles(patches: &[&ArchivedPatch]) -> Vec<String> {
+    patches.iter().map(|patch| patch.title.clone()).collect()
+}
+
+#[test]
+fn pages_newest_first() {
+    let archive = archive();
+
+    assert_eq!(archive.page_count(3), 2);
+    assert_eq!(
+        titles(&archive.page(1, 3).iter().collect::<Vec<_>>()),
+        ["Dota Plus Update", "7.33c", "7.33b"]
+    );
+    assert_eq!(
+        titles(&archive.page(2, 3).iter().collect::<Vec<_>>()),
+        ["7.33"]
+    );
+    assert!(archive.page(3, 3).is_empty());
+    assert_eq!(PatchArchive::in_memory().page_count(3), 1);
+}
+
+#[test]
+fn rerecording_replaces_by_gid() {
+    let mut archive = archive();
+
+    archive
+        .record(
+            &[patch("2", "7.33b", 2000, "Hero balance changes, revised")],
+            0,
+        )
+        .unwrap();
+
+    assert_eq!(archive.len(), 4);
+    let patch = archive.get("2").unwrap();
+    assert_eq!(patch.contents, "Hero balance changes, revised");
+    assert_eq!(
+        patch.contents_hash,
+        contents_hash("Hero balance changes, revised")
+    );
+}
+
+#[test]
+fn searches_titles_then_contents() {
+    let archive = archive();
+
+    assert_eq!(titles(&archive.search("7.33b", 5))[0], "7.33b");
+    assert_eq!(titles(&archive.search("733c", 5))[0], "7.33c");
+    // Title matches outrank contents matches
+    assert_eq!(titles(&archive.search("tormentor", 5)), ["7.33c", "7.33"]);
+    assert_eq!(
+        titles(&archive.search("dota plus", 5)),
+        ["Dota Plus Update"]
+    );
+    assert!(archive.search("battle pass", 5).is_empty());
+    assert!(archive.search("  ", 5).is_empty());
+}
+
+#[test]
+fn saves_and_loads() {
+    let path = std::env::temp_dir().join(format!("patches-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut archive = PatchArchive::load(&path).unwrap();
+    assert!(archive.is_empty());
+    archive
+        .record(&[patch("1", "7.33", 1000, "Notes")], 300)
+        .unwrap();
+
+    let loaded = PatchArchive::load(&path).unwrap();
+    assert_eq!(loaded.get("1"), archive.get("1"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn contents_hash_is_stable() {
+    assert_eq!(contents_hash(""), "cbf29ce484222325");
+    assert_eq!(contents_hash("a"), "af63dc4c8601ec8c");
+}
+
+#[tokio::test]
+async fn checker_archives_announced_articles() {
+    let news = FakeNews::with(vec![
+        patch("1", "7.33", 2000, "Notes"),
+        article("2", "Esports", at(2000), &["esports"]),
+    ]);
+    let mut discord = FakeDiscord::default();
+    discord.presences.insert(GuildId(1), vec![]);
+    let archive = PatchArchive::in_memory().shared();
+    let channels = [(GuildId(1), [10, 11])].into_iter().collect();
+    let mut checker = UpdateChecker::new(at(1000), channels).archive(archive.clone());
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    let archive = archive.lock().await;
+    assert_eq!(archive.len(), 1);
+    assert_eq!(archive.get("1").unwrap().contents, "Notes");
+    assert_eq!(archive.get("1").unwrap().maxlength, Some(0));
+}
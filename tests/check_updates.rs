@@ -0,0 +1,1021 @@
+mod common;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use common::{article, at, playing, FakeBuilds, FakeDiscord, FakeNews};
+use jenkins_bot::archive::PatchArchive;
+use jenkins_bot::builds::parse_build;
+use jenkins_bot::check_updates::{
+    get_users_playing, restart_ping, BuildChange, UpdateChecker, DEFERRED_PING_TIMEOUT_MINS,
+};
+use jenkins_bot::discord::MemberPresence;
+use jenkins_bot::feed_routes::{AnnounceRole, FeedRule, VoiceNotice};
+use jenkins_bot::news::{parse_news, Article, NewsError, PatchSize};
+use jenkins_bot::playing::GameMatcher;
+use jenkins_bot::poll_schedule::TimeWindow;
+use jenkins_bot::subscriptions::Subscriptions;
+use jenkins_bot::user_prefs::{PrefsStore, UserPrefs};
+use serenity::model::gateway::Activity;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const GUILD_A: u64 = 1;
+const GUILD_B: u64 = 2;
+const UNCONFIGURED_GUILD: u64 = 3;
+
+fn channels() -> HashMap<GuildId, [u64; 2]> {
+    [(GuildId(GUILD_A), [10, 11]), (GuildId(GUILD_B), [20, 21])]
+        .into_iter()
+        .collect()
+}
+
+fn discord() -> FakeDiscord {
+    let mut discord = FakeDiscord::default();
+    for guild in [GUILD_A, GUILD_B, UNCONFIGURED_GUILD] {
+        discord.presences.insert(GuildId(guild), vec![]);
+    }
+    discord
+}
+
+#[tokio::test]
+async fn announces_new_patch_notes() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    let announced = checker.poll(&news, &discord).await.unwrap();
+
+    assert_eq!(gids(&announced), ["1"]);
+    let embeds = discord.sent_to(11);
+    assert_eq!(embeds.len(), 1);
+    let embed = embeds[0].embed.as_ref().unwrap();
+    assert_eq!(embed.title, "7.33");
+    assert_eq!(embed.author.as_deref(), Some("Valve"));
+    assert!(embed
+        .description
+        .contains("https://store.steampowered.com/news/1"));
+}
+
+fn gids(articles: &[Article]) -> Vec<&str> {
+    articles
+        .iter()
+        .map(|article| article.gid.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn announces_every_new_patch_in_order() {
+    let news = FakeNews::with(vec![
+        article("3", "7.33b", at(3000), &["patchnotes"]),
+        article("x", "Battle Pass", at(2500), &["event"]),
+        article("2", "7.33", at(2000), &["patchnotes"]),
+    ]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    let announced = checker.poll(&news, &discord).await.unwrap();
+
+    assert_eq!(gids(&announced), ["2", "3"]);
+    let titles: Vec<String> = discord
+        .sent_to(11)
+        .into_iter()
+        .map(|message| message.embed.unwrap().title)
+        .collect();
+    assert_eq!(titles, ["7.33", "7.33b"]);
+
+    // An older patch that shows up late is still announced
+    news.set(vec![
+        article("3", "7.33b", at(3000), &["patchnotes"]),
+        article("1", "7.32e", at(1500), &["patchnotes"]),
+    ]);
+    assert_eq!(gids(&checker.poll(&news, &discord).await.unwrap()), ["1"]);
+}
+
+#[tokio::test]
+async fn batches_many_patches_into_a_digest() {
+    let news = FakeNews::with(
+        (1..=5)
+            .rev()
+            .map(|i| {
+                article(
+                    &i.to_string(),
+                    &format!("Patch {}", i),
+                    at(1000 + i),
+                    &["patchnotes"],
+                )
+            })
+            .collect(),
+    );
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert_eq!(checker.poll(&news, &discord).await.unwrap().len(), 5);
+
+    let embeds = discord.sent_to(11);
+    assert_eq!(embeds.len(), 1);
+    let embed = embeds[0].embed.as_ref().unwrap();
+    assert_eq!(embed.title, "5 new updates");
+    assert!(embed.description.starts_with("• [Patch 1]"));
+    assert!(embed
+        .description
+        .ends_with("(https://store.steampowered.com/news/5)"));
+    assert_eq!(embed.timestamp, Some(at(1005)));
+}
+
+#[tokio::test]
+async fn ignores_news_without_patchnotes_tag() {
+    let news = FakeNews::with(vec![
+        article("1", "The International", at(2000), &["esports"]),
+        article("2", "Untagged", at(2000), &[]),
+    ]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+    assert!(discord.sent().is_empty());
+}
+
+#[tokio::test]
+async fn ignores_patch_notes_from_before_startup() {
+    let news = FakeNews::with(vec![article("1", "7.32", at(500), &["patchnotes"])]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+    assert!(discord.sent().is_empty());
+}
+
+#[tokio::test]
+async fn does_not_announce_the_same_patch_twice() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert!(!checker.poll(&news, &discord).await.unwrap().is_empty());
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+    assert_eq!(discord.sent_to(11).len(), 1);
+
+    news.set(vec![
+        article("1", "7.33", at(2000), &["patchnotes"]),
+        article("2", "7.33b", at(3000), &["patchnotes"]),
+    ]);
+    let announced = checker.poll(&news, &discord).await.unwrap();
+    assert_eq!(gids(&announced), ["2"]);
+    assert_eq!(discord.sent_to(11).len(), 2);
+}
+
+#[tokio::test]
+async fn feed_errors_announce_nothing() {
+    let news = FakeNews::default();
+    news.fail(NewsError::Failed(String::from(
+        "Error checking for updates: timed out",
+    )));
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert!(checker.poll(&news, &discord).await.is_err());
+    assert!(discord.sent().is_empty());
+
+    news.fail(NewsError::RateLimited(Some(Duration::from_secs(30))));
+    assert!(matches!(
+        checker.poll(&news, &discord).await,
+        Err(NewsError::RateLimited(Some(_)))
+    ));
+    assert!(discord.sent().is_empty());
+}
+
+#[tokio::test]
+async fn unchanged_feed_announces_nothing() {
+    let news = FakeNews::default();
+    news.not_modified();
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+    assert!(discord.sent().is_empty());
+}
+
+#[tokio::test]
+async fn fans_out_to_every_configured_guild() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    discord.presences.insert(
+        GuildId(GUILD_B),
+        vec![playing(200, "Dota 2"), playing(201, "Dota 2")],
+    );
+    discord
+        .presences
+        .insert(GuildId(UNCONFIGURED_GUILD), vec![playing(300, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    assert_eq!(discord.sent_to(11).len(), 1);
+    assert_eq!(discord.sent_to(21).len(), 1);
+    assert_eq!(
+        discord.sent_to(10)[0].content.as_deref(),
+        Some("Attention <@100>: You need to restart dota. There is an update!")
+    );
+    assert_eq!(
+        discord.sent_to(20)[0].content.as_deref(),
+        Some("Attention <@200> and <@201>: You need to restart dota. There is an update!")
+    );
+    // Guilds without configured channels get nothing
+    assert!(discord
+        .sent()
+        .iter()
+        .all(|(channel, _)| [10, 11, 20, 21].contains(&channel.0)));
+}
+
+#[tokio::test]
+async fn does_not_ping_when_nobody_is_playing() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    assert!(discord.sent_to(10).is_empty());
+    assert!(discord.sent_to(20).is_empty());
+    assert_eq!(
+        discord
+            .sent()
+            .iter()
+            .filter(|(channel, _)| *channel == ChannelId(11) || *channel == ChannelId(21))
+            .count(),
+        2
+    );
+}
+
+fn rule(channel: u64, tags: &[&str]) -> FeedRule {
+    FeedRule {
+        channel_id: ChannelId(channel),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        feeds: vec![],
+        title: None,
+        restart_ping: false,
+    }
+}
+
+#[tokio::test]
+async fn routes_items_per_guild() {
+    let mut esports = article("2", "TI Qualifiers", at(2000), &["esports"]);
+    esports.feedname = String::from("pcgamer");
+    let news = FakeNews::with(vec![
+        article("1", "7.33", at(2000), &["patchnotes"]),
+        esports,
+        article("3", "Dota Plus Update", at(2000), &["announcements"]),
+    ]);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut from_valve = rule(13, &[]);
+    from_valve.feeds = vec![String::from("steam_community_announcements")];
+    from_valve.title = Some(regex::Regex::new(r"^\d+\.\d+").unwrap());
+    let routes = [(
+        GuildId(GUILD_A),
+        vec![rule(12, &["esports", "announcements"]), from_valve],
+    )]
+    .into_iter()
+    .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).routes(routes);
+
+    assert_eq!(checker.poll(&news, &discord).await.unwrap().len(), 3);
+
+    let titles = |channel| -> Vec<String> {
+        discord
+            .sent_to(channel)
+            .into_iter()
+            .map(|message| message.embed.unwrap().title)
+            .collect()
+    };
+    assert_eq!(titles(12), ["TI Qualifiers", "Dota Plus Update"]);
+    assert_eq!(titles(13), ["7.33"]);
+    assert!(titles(11).is_empty());
+    // Guilds without routes still get just the patch notes
+    assert_eq!(titles(21), ["7.33"]);
+    // None of guild A's routes ask for a restart ping
+    assert!(discord.sent_to(10).is_empty());
+}
+
+#[tokio::test]
+async fn routes_send_each_item_to_a_channel_once() {
+    let news = FakeNews::with(vec![article(
+        "1",
+        "7.33",
+        at(2000),
+        &["patchnotes", "announcements"],
+    )]);
+    let discord = discord();
+    let mut patches = rule(12, &["patchnotes"]);
+    patches.restart_ping = true;
+    let routes = [(
+        GuildId(GUILD_A),
+        vec![patches, rule(12, &["announcements"])],
+    )]
+    .into_iter()
+    .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).routes(routes);
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    assert_eq!(discord.sent_to(12).len(), 1);
+}
+
+#[test]
+fn classifies_patches_by_version() {
+    let size = |title| article("1", title, at(0), &["patchnotes"]).patch_size();
+    assert_eq!(size("Gameplay Patch 7.36"), PatchSize::Major);
+    assert_eq!(size("7.33 Gameplay Update"), PatchSize::Major);
+    assert_eq!(size("Gameplay Patch 7.36a"), PatchSize::Minor);
+    assert_eq!(size("Dota 2 Update - 5/24/2024"), PatchSize::Minor);
+}
+
+#[tokio::test]
+async fn mentions_announce_role_for_major_patches() {
+    let news = FakeNews::with(vec![article("1", "7.36", at(2000), &["patchnotes"])]);
+    let discord = discord();
+    let roles = [
+        (
+            GuildId(GUILD_A),
+            AnnounceRole {
+                role_id: RoleId(500),
+                minor: false,
+            },
+        ),
+        (
+            GuildId(GUILD_B),
+            AnnounceRole {
+                role_id: RoleId(600),
+                minor: true,
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).announce_roles(roles);
+
+    checker.poll(&news, &discord).await.unwrap();
+    assert_eq!(discord.sent_to(11)[0].content.as_deref(), Some("<@&500>"));
+    assert_eq!(discord.sent_to(21)[0].content.as_deref(), Some("<@&600>"));
+
+    // Only the guild that asked for minor patches hears about them
+    news.set(vec![article("2", "7.36a", at(3000), &["patchnotes"])]);
+    checker.poll(&news, &discord).await.unwrap();
+    assert_eq!(discord.sent_to(11)[1].content, None);
+    assert_eq!(discord.sent_to(21)[1].content.as_deref(), Some("<@&600>"));
+}
+
+#[tokio::test]
+async fn crossposts_in_announcement_channels_when_enabled() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord.announcement_channels = vec![ChannelId(11), ChannelId(21)];
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels())
+        .crosspost([GuildId(GUILD_A)].into_iter().collect());
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    // Only guild A's update is published; its restart ping isn't in an announcement channel
+    let crossposted = discord.crossposted.lock().unwrap().clone();
+    assert_eq!(crossposted.len(), 1);
+    let (channel, message_id) = crossposted[0];
+    assert_eq!(channel, ChannelId(11));
+    assert_eq!(discord.sent()[message_id.0 as usize - 1].0, ChannelId(11));
+}
+
+fn with_contents(mut article: Article, contents: &str) -> Article {
+    article.contents = contents.to_string();
+    article
+}
+
+#[tokio::test]
+async fn replies_to_edited_patch_notes_with_a_diff() {
+    let original = article("1", "7.33", at(2000), &["patchnotes"]);
+    let news = FakeNews::with(vec![with_contents(original.clone(), "Axe\n- Armor 2")]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+    checker.poll(&news, &discord).await.unwrap();
+
+    news.set(vec![with_contents(original, "Axe\n- Armor 3")]);
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+
+    let sent = discord.sent_to(11);
+    assert_eq!(sent.len(), 2);
+    let edit = &sent[1];
+    let embed = edit.embed.as_ref().unwrap();
+    assert_eq!(embed.title, "Updated patch notes: 7.33");
+    assert!(embed
+        .description
+        .ends_with("```diff\n- - Armor 2\n+ - Armor 3\n```"));
+    // Replies to the original announcement, which was the first message sent
+    assert_eq!(edit.reply_to, Some((ChannelId(11), MessageId(1))));
+
+    // Seen once, the edit isn't posted again
+    checker.poll(&news, &discord).await.unwrap();
+    assert_eq!(discord.sent_to(11).len(), 2);
+}
+
+#[tokio::test]
+async fn notices_edits_to_patches_announced_before_a_restart() {
+    let original = with_contents(article("1", "7.33", at(2000), &["patchnotes"]), "Axe");
+    let archive = PatchArchive::in_memory().shared();
+    archive
+        .lock()
+        .await
+        .record(std::slice::from_ref(&original), 0)
+        .unwrap();
+    let news = FakeNews::with(vec![with_contents(original, "Axe\nBane")]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels()).archive(archive.clone());
+
+    // Not announced again, but the edit is
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+    let sent = discord.sent_to(11);
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].reply_to, None);
+    assert!(sent[0]
+        .embed
+        .as_ref()
+        .unwrap()
+        .description
+        .contains("+ Bane"));
+    assert_eq!(archive.lock().await.get("1").unwrap().contents, "Axe\nBane");
+}
+
+#[tokio::test]
+async fn rebaselines_patches_archived_at_another_length() {
+    let original = with_contents(article("1", "7.33", at(2000), &["patchnotes"]), "Axe...");
+    let archive = PatchArchive::in_memory().shared();
+    archive
+        .lock()
+        .await
+        .record(std::slice::from_ref(&original), 5)
+        .unwrap();
+    let news = FakeNews::with(vec![with_contents(original, "Axe\n- Armor 3")]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels())
+        .news_maxlength(0)
+        .archive(archive.clone());
+
+    assert!(checker.poll(&news, &discord).await.unwrap().is_empty());
+
+    assert!(discord.sent().is_empty());
+    let patch = archive.lock().await.get("1").cloned().unwrap();
+    assert_eq!(patch.contents, "Axe\n- Armor 3");
+    assert_eq!(patch.maxlength, Some(0));
+}
+
+#[tokio::test]
+async fn ignores_contents_that_were_only_cut_short() {
+    let original = article("1", "7.33", at(2000), &["patchnotes"]);
+    let news = FakeNews::with(vec![with_contents(original.clone(), "Axe\n- Arm...")]);
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+    checker.poll(&news, &discord).await.unwrap();
+
+    news.set(vec![with_contents(original.clone(), "Axe\n- Armor 3")]);
+    checker.poll(&news, &discord).await.unwrap();
+    assert_eq!(discord.sent_to(11).len(), 1);
+
+    // Later edits are still noticed
+    news.set(vec![with_contents(original, "Axe\n- Armor 4")]);
+    checker.poll(&news, &discord).await.unwrap();
+    assert_eq!(discord.sent_to(11).len(), 2);
+}
+
+#[tokio::test]
+async fn mentions_subscribers_of_changed_heroes() {
+    let mut subscriptions = Subscriptions::in_memory();
+    subscriptions
+        .subscribe(GuildId(GUILD_A), UserId(100), "Axe")
+        .unwrap();
+    subscriptions
+        .subscribe(GuildId(GUILD_A), UserId(101), "Lina")
+        .unwrap();
+    subscriptions
+        .subscribe(GuildId(GUILD_B), UserId(102), "Bane")
+        .unwrap();
+    let news = FakeNews::with(vec![with_contents(
+        article("1", "7.33", at(2000), &["patchnotes"]),
+        "Hero Updates\nAxe\n- Armor 3\nBane\n- Nightmare lasts longer",
+    )]);
+    let discord = discord();
+    let mut checker =
+        UpdateChecker::new(at(1000), channels()).subscriptions(subscriptions.shared());
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    // Followers are mentioned under the announcement
+    let sent = discord.sent_to(11);
+    assert_eq!(sent.len(), 2);
+    let content = sent[1].content.as_deref().unwrap();
+    assert!(content.contains("<@100>: Axe"));
+    assert!(!content.contains("<@101>"));
+    assert!(discord.sent_to(21)[1]
+        .content
+        .as_deref()
+        .unwrap()
+        .contains("<@102>: Bane"));
+    assert!(discord.sent_to(10).is_empty());
+}
+
+#[tokio::test]
+async fn build_changes_follow_restart_routes() {
+    let builds = FakeBuilds::with(5000);
+    let mut discord = discord();
+    for guild in [GUILD_A, GUILD_B, UNCONFIGURED_GUILD] {
+        discord
+            .presences
+            .insert(GuildId(guild), vec![playing(100 + guild, "Dota 2")]);
+    }
+    let mut patches = rule(12, &["patchnotes"]);
+    patches.restart_ping = true;
+    let routes = [
+        (
+            GuildId(GUILD_A),
+            vec![patches, rule(13, &["announcements"])],
+        ),
+        (GuildId(GUILD_B), vec![rule(22, &["patchnotes"])]),
+        (GuildId(UNCONFIGURED_GUILD), vec![rule(32, &["patchnotes"])]),
+    ]
+    .into_iter()
+    .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).routes(routes);
+    checker.poll_build(&builds, &discord).await.unwrap();
+
+    builds.set(5001);
+    checker.poll_build(&builds, &discord).await.unwrap();
+
+    assert_eq!(discord.sent_to(12).len(), 1);
+    assert!(discord.sent_to(13).is_empty());
+    assert_eq!(
+        discord.sent_to(10)[0].allowed_users,
+        Some(vec![UserId(101)])
+    );
+    // Guild B's routes don't want restart pings
+    assert!(discord.sent_to(21).is_empty());
+    assert!(discord.sent_to(22).is_empty());
+    assert!(discord.sent_to(20).is_empty());
+    assert!(discord.sent_to(32).is_empty());
+}
+
+#[tokio::test]
+async fn routes_only_guilds_are_pinged_in_their_restart_route() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(UNCONFIGURED_GUILD), vec![playing(100, "Dota 2")]);
+    let mut patches = rule(32, &["patchnotes"]);
+    patches.restart_ping = true;
+    let routes = [(GuildId(UNCONFIGURED_GUILD), vec![patches])]
+        .into_iter()
+        .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).routes(routes);
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    let sent = discord.sent_to(32);
+    assert_eq!(sent.len(), 2);
+    assert!(sent[1].content.as_deref().unwrap().contains("<@100>"));
+}
+
+#[tokio::test]
+async fn announces_build_changes_without_patch_notes() {
+    let builds = FakeBuilds::with(5000);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    // The build at startup is only remembered
+    assert_eq!(checker.poll_build(&builds, &discord).await.unwrap(), None);
+    assert!(discord.sent().is_empty());
+
+    builds.set(5001);
+    assert_eq!(
+        checker.poll_build(&builds, &discord).await.unwrap(),
+        Some(BuildChange {
+            from: 5000,
+            to: 5001
+        })
+    );
+    assert_eq!(
+        discord.sent_to(11)[0].embed.as_ref().unwrap().title,
+        "Dota client updated (build 5000 → 5001)"
+    );
+    assert_eq!(discord.sent_to(21).len(), 1);
+    assert_eq!(discord.sent_to(10).len(), 1);
+
+    assert_eq!(checker.poll_build(&builds, &discord).await.unwrap(), None);
+    assert_eq!(discord.sent().len(), 3);
+}
+
+#[tokio::test]
+async fn build_change_after_patch_notes_is_the_same_update() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let builds = FakeBuilds::with(5000);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+    checker.poll_build(&builds, &discord).await.unwrap();
+
+    checker.poll(&news, &discord).await.unwrap();
+    builds.set(5001);
+    assert_eq!(checker.poll_build(&builds, &discord).await.unwrap(), None);
+
+    assert_eq!(discord.sent_to(11).len(), 1);
+    assert_eq!(discord.sent_to(10).len(), 1);
+}
+
+#[tokio::test]
+async fn announces_every_build_change_after_patch_notes() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let builds = FakeBuilds::with(5000);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+    checker.poll_build(&builds, &discord).await.unwrap();
+
+    checker.poll(&news, &discord).await.unwrap();
+    builds.set(5001);
+    assert_eq!(checker.poll_build(&builds, &discord).await.unwrap(), None);
+    // Only the first build change is the one the notes were for
+    builds.set(5002);
+    assert_eq!(
+        checker.poll_build(&builds, &discord).await.unwrap(),
+        Some(BuildChange {
+            from: 5001,
+            to: 5002
+        })
+    );
+
+    assert_eq!(discord.sent_to(11).len(), 2);
+    assert_eq!(discord.sent_to(10).len(), 2);
+}
+
+#[tokio::test]
+async fn announces_and_pings_every_build_change() {
+    let builds = FakeBuilds::with(5000);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+    checker.poll_build(&builds, &discord).await.unwrap();
+
+    builds.set(5001);
+    assert!(checker
+        .poll_build(&builds, &discord)
+        .await
+        .unwrap()
+        .is_some());
+    builds.set(5002);
+    assert!(checker
+        .poll_build(&builds, &discord)
+        .await
+        .unwrap()
+        .is_some());
+
+    assert_eq!(discord.sent_to(11).len(), 2);
+    assert_eq!(discord.sent_to(10).len(), 2);
+}
+
+#[tokio::test]
+async fn patch_notes_after_build_change_do_not_ping_again() {
+    let news = FakeNews::default();
+    let builds = FakeBuilds::with(5000);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+    checker.poll_build(&builds, &discord).await.unwrap();
+
+    builds.set(5001);
+    checker.poll_build(&builds, &discord).await.unwrap();
+    news.set(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    assert!(!checker.poll(&news, &discord).await.unwrap().is_empty());
+
+    // Both announcements are posted, but players are only pinged once
+    assert_eq!(discord.sent_to(11).len(), 2);
+    assert_eq!(discord.sent_to(10).len(), 1);
+}
+
+#[tokio::test]
+async fn build_errors_announce_nothing() {
+    let builds = FakeBuilds::default();
+    builds.fail(NewsError::RateLimited(None));
+    let discord = discord();
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    assert!(checker.poll_build(&builds, &discord).await.is_err());
+    assert!(discord.sent().is_empty());
+}
+
+fn in_match(user_id: u64) -> MemberPresence {
+    let mut presence = playing(user_id, "Dota 2");
+    presence.activities[0].details = Some(String::from("Playing as Axe"));
+    presence
+}
+
+#[tokio::test]
+async fn defers_pings_for_players_in_a_match() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord.presences.insert(
+        GuildId(GUILD_A),
+        vec![in_match(100), playing(101, "Dota 2")],
+    );
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    checker.poll(&news, &discord).await.unwrap();
+    let pings = discord.sent_to(10);
+    assert_eq!(pings.len(), 1);
+    assert_eq!(pings[0].allowed_users, Some(vec![UserId(101)]));
+
+    // Still in the match
+    checker.ping_deferred(Utc::now(), &discord).await;
+    assert_eq!(discord.sent_to(10).len(), 1);
+
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    checker.ping_deferred(Utc::now(), &discord).await;
+    let pings = discord.sent_to(10);
+    assert_eq!(pings.len(), 2);
+    assert_eq!(pings[1].allowed_users, Some(vec![UserId(100)]));
+
+    // Delivered once
+    checker.ping_deferred(Utc::now(), &discord).await;
+    assert_eq!(discord.sent_to(10).len(), 2);
+}
+
+#[tokio::test]
+async fn deferred_pings_are_sent_after_a_timeout() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![in_match(100)]);
+    let mut checker = UpdateChecker::new(at(1000), channels());
+
+    checker.poll(&news, &discord).await.unwrap();
+    assert!(discord.sent_to(10).is_empty());
+
+    let later = Utc::now() + ChronoDuration::minutes(DEFERRED_PING_TIMEOUT_MINS + 1);
+    checker.ping_deferred(later, &discord).await;
+    let pings = discord.sent_to(10);
+    assert_eq!(pings.len(), 1);
+    assert_eq!(pings[0].allowed_users, Some(vec![UserId(100)]));
+}
+
+#[tokio::test]
+async fn holds_pings_until_quiet_hours_end() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord.presences.insert(
+        GuildId(GUILD_A),
+        vec![playing(100, "Dota 2"), playing(101, "Dota 2")],
+    );
+    let now = Utc::now().time();
+    let mut prefs = PrefsStore::in_memory();
+    prefs
+        .set(
+            UserId(100),
+            UserPrefs {
+                timezone: None,
+                quiet_hours: Some(TimeWindow {
+                    start: now - ChronoDuration::hours(1),
+                    end: now + ChronoDuration::hours(1),
+                }),
+            },
+        )
+        .unwrap();
+    let mut checker = UpdateChecker::new(at(1000), channels()).prefs(prefs.shared());
+
+    checker.poll(&news, &discord).await.unwrap();
+    let pings = discord.sent_to(10);
+    assert_eq!(pings.len(), 1);
+    assert_eq!(pings[0].allowed_users, Some(vec![UserId(101)]));
+
+    checker.ping_deferred(Utc::now(), &discord).await;
+    assert_eq!(discord.sent_to(10).len(), 1);
+
+    let later = Utc::now() + ChronoDuration::hours(2);
+    checker.ping_deferred(later, &discord).await;
+    let pings = discord.sent_to(10);
+    assert_eq!(pings.len(), 2);
+    assert_eq!(pings[1].allowed_users, Some(vec![UserId(100)]));
+}
+
+#[tokio::test]
+async fn tells_players_in_voice_to_restart_there() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord.presences.insert(
+        GuildId(GUILD_A),
+        vec![
+            playing(100, "Dota 2"),
+            playing(101, "Dota 2"),
+            playing(102, "Dota 2"),
+        ],
+    );
+    discord.voice.insert(
+        GuildId(GUILD_A),
+        [(UserId(100), ChannelId(50)), (UserId(101), ChannelId(50))]
+            .into_iter()
+            .collect(),
+    );
+    let notices = [(GuildId(GUILD_A), VoiceNotice { replace_ping: true })]
+        .into_iter()
+        .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).voice_notices(notices);
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    let voice = discord.sent_to(50);
+    assert_eq!(voice.len(), 1);
+    assert_eq!(voice[0].allowed_users, Some(vec![UserId(100), UserId(101)]));
+    let pings = discord.sent_to(10);
+    assert_eq!(pings.len(), 1);
+    assert_eq!(pings[0].allowed_users, Some(vec![UserId(102)]));
+}
+
+#[tokio::test]
+async fn voice_notices_can_add_to_the_ping_channel() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = discord();
+    discord
+        .presences
+        .insert(GuildId(GUILD_A), vec![playing(100, "Dota 2")]);
+    discord.voice.insert(
+        GuildId(GUILD_A),
+        [(UserId(100), ChannelId(50))].into_iter().collect(),
+    );
+    let notices = [(GuildId(GUILD_A), VoiceNotice::default())]
+        .into_iter()
+        .collect();
+    let mut checker = UpdateChecker::new(at(1000), channels()).voice_notices(notices);
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    assert_eq!(discord.sent_to(50).len(), 1);
+    assert_eq!(discord.sent_to(10).len(), 1);
+}
+
+#[test]
+fn restart_ping_lists_players() {
+    let contents = |players: &[UserId]| -> Vec<String> {
+        restart_ping(players)
+            .into_iter()
+            .map(|message| message.content.unwrap())
+            .collect()
+    };
+    assert!(restart_ping(&[]).is_empty());
+    assert_eq!(
+        contents(&[UserId(1)]),
+        ["Attention <@1>: You need to restart dota. There is an update!"]
+    );
+    assert_eq!(
+        contents(&[UserId(1), UserId(2)]),
+        ["Attention <@1> and <@2>: You need to restart dota. There is an update!"]
+    );
+    assert_eq!(
+        contents(&[UserId(1), UserId(2), UserId(3)]),
+        ["Attention <@1>, <@2> and <@3>: You need to restart dota. There is an update!"]
+    );
+}
+
+#[test]
+fn restart_ping_splits_large_guilds() {
+    let players: Vec<UserId> = (0..500)
+        .map(|i| UserId(100_000_000_000_000_000 + i))
+        .collect();
+
+    let messages = restart_ping(&players);
+
+    assert!(messages.len() > 1);
+    let mut mentioned = vec![];
+    for message in &messages {
+        let content = message.content.as_deref().unwrap();
+        assert!(content.chars().count() <= 2000);
+        assert!(content.ends_with("There is an update!"));
+        let allowed = message.allowed_users.clone().unwrap();
+        for user in &allowed {
+            assert!(content.contains(&format!("<@{}>", user)));
+        }
+        mentioned.extend(allowed);
+    }
+    assert_eq!(mentioned, players);
+}
+
+#[test]
+fn only_humans_playing_dota_are_pinged() {
+    let mut discord = FakeDiscord::default();
+    let mut bot = playing(2, "Dota 2");
+    bot.bot = true;
+    discord.presences.insert(
+        GuildId(GUILD_A),
+        vec![
+            playing(1, "Dota 2"),
+            bot,
+            playing(3, "Deadlock"),
+            MemberPresence {
+                user_id: UserId(4),
+                bot: false,
+                activities: vec![],
+            },
+            MemberPresence {
+                user_id: UserId(5),
+                bot: false,
+                activities: vec![Activity::playing("Spotify"), Activity::playing("Dota 2")],
+            },
+        ],
+    );
+
+    let playing = get_users_playing(&discord, &GameMatcher::default());
+
+    let user_ids: Vec<UserId> = playing[&GuildId(GUILD_A)]
+        .iter()
+        .map(|player| player.user_id)
+        .collect();
+    assert_eq!(user_ids, vec![UserId(1), UserId(5)]);
+}
+
+#[test]
+fn parses_steam_news_feed() {
+    let body = r#"{
+        "appnews": {
+            "appid": 570,
+            "newsitems": [
+                {
+                    "gid": "5124",
+                    "title": "Dota 2 Update - 7.33",
+                    "url": "https://steamstore-a.akamaihd.net/news/externalpost/5124",
+                    "author": "Valve",
+                    "contents": "Gameplay changes",
+                    "feedname": "steam_community_announcements",
+                    "date": 1682467200,
+                    "tags": ["patchnotes"]
+                },
+                {
+                    "gid": "5125",
+                    "title": "Missing author",
+                    "url": "https://example.com",
+                    "date": 1682467200
+                },
+                {
+                    "gid": "5126",
+                    "title": "Untagged post",
+                    "url": "https://example.com/5126",
+                    "author": "Valve",
+                    "date": 1682467300
+                }
+            ]
+        }
+    }"#;
+
+    let articles = parse_news(body).unwrap();
+
+    assert_eq!(articles.len(), 2);
+    assert_eq!(articles[0].gid, "5124");
+    assert_eq!(articles[0].title, "Dota 2 Update - 7.33");
+    assert_eq!(articles[0].date, at(1682467200));
+    assert!(articles[0].has_tag("patchnotes"));
+    assert_eq!(articles[1].gid, "5126");
+    assert!(articles[1].tags.is_empty());
+}
+
+#[test]
+fn rejects_malformed_news_feed() {
+    assert!(parse_news("not json").is_err());
+    assert!(parse_news(r#"{"other": {}}"#).is_err());
+    assert!(parse_news(r#"{"appnews": {"newsitems": 5}}"#).is_err());
+}
+
+#[test]
+fn parses_build_checks() {
+    assert_eq!(
+        parse_build(
+            r#"{"response":{"success":true,"up_to_date":false,"version_is_listable":false,"required_version":5786,"message":"Your client is out of date"}}"#
+        ),
+        Ok(5786)
+    );
+    assert!(parse_build(r#"{"response":{"success":false}}"#).is_err());
+    assert!(parse_build(r#"{"response":{"success":true,"required_version":"new"}}"#).is_err());
+    assert!(parse_build("<html>").is_err());
+}
@@ -0,0 +1,327 @@
+//! In-memory stand-ins for Steam and Discord so the bot's logic can be tested without a network.
+#![allow(dead_code)]
+
+use chrono::{DateTime, TimeZone, Utc};
+use jenkins_bot::builds::BuildSource;
+use jenkins_bot::discord::Embed;
+use jenkins_bot::discord::{
+    ChannelInfo, ChannelSource, MemberPresence, MessageInfo, MessageSink, OutgoingMessage,
+    PresenceSource,
+};
+use jenkins_bot::news::{Article, NewsError, NewsFetch, NewsSource};
+use jenkins_bot::webhooks::AnnouncementSink;
+use serenity::async_trait;
+use serenity::model::channel::ChannelType;
+use serenity::model::gateway::Activity;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::{Error, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Milliseconds between the Unix epoch and Discord's.
+const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+
+/// A snowflake ID created at `unix_seconds`.
+pub fn snowflake(unix_seconds: i64) -> u64 {
+    ((unix_seconds as u64) * 1000 - DISCORD_EPOCH) << 22
+}
+
+pub fn at(unix_seconds: i64) -> DateTime<Utc> {
+    Utc.timestamp(unix_seconds, 0)
+}
+
+pub fn article(gid: &str, title: &str, date: DateTime<Utc>, tags: &[&str]) -> Article {
+    Article {
+        gid: gid.to_string(),
+        title: title.to_string(),
+        author: String::from("Valve"),
+        url: format!("https://store.steampowered.com/news/{}", gid),
+        feedname: String::from("steam_community_announcements"),
+        contents: String::new(),
+        date,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+pub fn channel(id: u64, kind: ChannelType, last_message_id: Option<u64>) -> ChannelInfo {
+    ChannelInfo {
+        id: ChannelId(id),
+        kind,
+        last_message_id: last_message_id.map(MessageId),
+        archived_at: None,
+    }
+}
+
+/// A message in `channel_id` sent at `unix_seconds`. `offset` is added to its snowflake so
+/// several messages can share a second.
+pub fn message(
+    channel_id: u64,
+    unix_seconds: i64,
+    offset: u64,
+    author: u64,
+    content: &str,
+) -> MessageInfo {
+    MessageInfo {
+        id: MessageId(snowflake(unix_seconds) + offset),
+        channel_id: ChannelId(channel_id),
+        author_id: UserId(author),
+        content: content.to_string(),
+        timestamp: unix_seconds,
+    }
+}
+
+pub fn playing(user_id: u64, game: &str) -> MemberPresence {
+    MemberPresence {
+        user_id: UserId(user_id),
+        bot: false,
+        activities: vec![Activity::playing(game)],
+    }
+}
+
+#[derive(Default)]
+pub struct FakeNews {
+    feed: Mutex<Option<std::result::Result<NewsFetch, NewsError>>>,
+}
+
+impl FakeNews {
+    pub fn with(articles: Vec<Article>) -> Self {
+        let news = Self::default();
+        news.set(articles);
+        news
+    }
+
+    pub fn set(&self, articles: Vec<Article>) {
+        *self.feed.lock().unwrap() = Some(Ok(NewsFetch::Updated(articles)));
+    }
+
+    pub fn not_modified(&self) {
+        *self.feed.lock().unwrap() = Some(Ok(NewsFetch::NotModified));
+    }
+
+    pub fn fail(&self, error: NewsError) {
+        *self.feed.lock().unwrap() = Some(Err(error));
+    }
+}
+
+#[async_trait]
+impl NewsSource for FakeNews {
+    async fn fetch_news(&self) -> std::result::Result<NewsFetch, NewsError> {
+        self.feed
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or(Ok(NewsFetch::Updated(vec![])))
+    }
+}
+
+#[derive(Default)]
+pub struct FakeBuilds {
+    build: Mutex<Option<std::result::Result<u64, NewsError>>>,
+}
+
+impl FakeBuilds {
+    pub fn with(build: u64) -> Self {
+        let builds = Self::default();
+        builds.set(build);
+        builds
+    }
+
+    pub fn set(&self, build: u64) {
+        *self.build.lock().unwrap() = Some(Ok(build));
+    }
+
+    pub fn fail(&self, error: NewsError) {
+        *self.build.lock().unwrap() = Some(Err(error));
+    }
+}
+
+#[async_trait]
+impl BuildSource for FakeBuilds {
+    async fn fetch_build(&self) -> std::result::Result<u64, NewsError> {
+        self.build.lock().unwrap().clone().unwrap_or(Ok(0))
+    }
+}
+
+/// Records announcements. Clones share the record, so one can be handed to the checker.
+#[derive(Clone, Default)]
+pub struct FakeSink {
+    pub announced: Arc<Mutex<Vec<Embed>>>,
+}
+
+impl FakeSink {
+    pub fn titles(&self) -> Vec<String> {
+        self.announced
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|embed| embed.title.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AnnouncementSink for FakeSink {
+    async fn announce(&self, embed: &Embed) -> std::result::Result<(), String> {
+        self.announced.lock().unwrap().push(embed.clone());
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct FakeDiscord {
+    pub channels: HashMap<GuildId, Vec<ChannelInfo>>,
+    pub active_threads: HashMap<GuildId, Vec<ChannelInfo>>,
+    pub archived_threads: HashMap<ChannelId, Vec<ChannelInfo>>,
+    pub messages: HashMap<ChannelId, Vec<MessageInfo>>,
+    pub presences: HashMap<GuildId, Vec<MemberPresence>>,
+    pub voice: HashMap<GuildId, HashMap<UserId, ChannelId>>,
+    /// Channels whose history requests fail, e.g. for missing permissions.
+    pub forbidden: Vec<ChannelId>,
+    pub announcement_channels: Vec<ChannelId>,
+    /// Every message sent, in order. A message's ID is its position in this, counting from 1.
+    pub sent: Mutex<Vec<(ChannelId, OutgoingMessage)>>,
+    pub crossposted: Mutex<Vec<(ChannelId, MessageId)>>,
+    /// Every history request made, as (channel, before).
+    pub history_requests: Mutex<Vec<(ChannelId, Option<MessageId>)>>,
+}
+
+impl FakeDiscord {
+    pub fn add_messages(&mut self, messages: Vec<MessageInfo>) {
+        for message in messages {
+            self.messages
+                .entry(message.channel_id)
+                .or_default()
+                .push(message);
+        }
+    }
+
+    pub fn sent(&self) -> Vec<(ChannelId, OutgoingMessage)> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn sent_to(&self, channel_id: u64) -> Vec<OutgoingMessage> {
+        self.sent()
+            .into_iter()
+            .filter(|(channel, _)| *channel == ChannelId(channel_id))
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    pub fn scanned_channels(&self) -> Vec<ChannelId> {
+        let mut channels: Vec<ChannelId> = self
+            .history_requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(channel, _)| *channel)
+            .collect();
+        channels.sort();
+        channels.dedup();
+        channels
+    }
+}
+
+#[async_trait]
+impl ChannelSource for FakeDiscord {
+    async fn guild_channels(&self, guild_id: GuildId) -> Result<Vec<ChannelInfo>> {
+        self.channels
+            .get(&guild_id)
+            .cloned()
+            .ok_or(Error::Other("unknown guild"))
+    }
+
+    async fn active_threads(&self, guild_id: GuildId) -> Result<Vec<ChannelInfo>> {
+        Ok(self
+            .active_threads
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn archived_threads(
+        &self,
+        channel_id: ChannelId,
+        limit: u64,
+    ) -> Result<Vec<ChannelInfo>> {
+        let mut threads = self
+            .archived_threads
+            .get(&channel_id)
+            .cloned()
+            .unwrap_or_default();
+        threads.truncate(limit as usize);
+        Ok(threads)
+    }
+
+    async fn messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: u64,
+    ) -> Result<Vec<MessageInfo>> {
+        self.history_requests
+            .lock()
+            .unwrap()
+            .push((channel_id, before));
+        if self.forbidden.contains(&channel_id) {
+            return Err(Error::Other("missing access"));
+        }
+        let mut messages: Vec<MessageInfo> = self
+            .messages
+            .get(&channel_id)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|message| before.is_none_or(|before| message.id < before))
+            .collect();
+        messages.sort_by_key(|message| std::cmp::Reverse(message.id));
+        messages.truncate(limit as usize);
+        Ok(messages)
+    }
+}
+
+#[async_trait]
+impl MessageSink for FakeDiscord {
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: &OutgoingMessage,
+    ) -> Result<MessageId> {
+        let mut sent = self.sent.lock().unwrap();
+        sent.push((channel_id, message.clone()));
+        Ok(MessageId(sent.len() as u64))
+    }
+
+    async fn is_announcement_channel(&self, channel_id: ChannelId) -> Result<bool> {
+        Ok(self.announcement_channels.contains(&channel_id))
+    }
+
+    async fn crosspost(&self, channel_id: ChannelId, message_id: MessageId) -> Result<()> {
+        self.crossposted
+            .lock()
+            .unwrap()
+            .push((channel_id, message_id));
+        Ok(())
+    }
+}
+
+impl PresenceSource for FakeDiscord {
+    fn guilds(&self) -> Vec<GuildId> {
+        let mut guilds: Vec<GuildId> = self
+            .channels
+            .keys()
+            .chain(self.presences.keys())
+            .copied()
+            .collect();
+        guilds.sort();
+        guilds.dedup();
+        guilds
+    }
+
+    fn presences(&self, guild_id: GuildId) -> Vec<MemberPresence> {
+        self.presences.get(&guild_id).cloned().unwrap_or_default()
+    }
+
+    fn voice_channels(&self, guild_id: GuildId) -> HashMap<UserId, ChannelId> {
+        self.voice.get(&guild_id).cloned().unwrap_or_default()
+    }
+}
@@ -0,0 +1,339 @@
+use jenkins_bot::config::{Config, ConfigError};
+use jenkins_bot::news::news_url;
+use jenkins_bot::webhooks::WebhookKind;
+use serenity::model::gateway::Activity;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn load(toml: &str, env: &[(&str, &str)]) -> Result<Config, ConfigError> {
+    let env: HashMap<String, String> = env
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    Config::from_toml(toml, |name| env.get(name).cloned())
+}
+
+#[test]
+fn defaults_only_need_a_token() {
+    let config = load("", &[("DISCORD_TOKEN", "abc")]).unwrap();
+
+    assert_eq!(config.discord_token, "abc");
+    assert_eq!(config.poll_interval, Duration::from_secs(60));
+    assert_eq!(config.poll_jitter, Duration::ZERO);
+    assert_eq!(
+        news_url(&config),
+        "http://api.steampowered.com/ISteamNews/GetNewsForApp/v0002/?appid=570&count=10&maxlength=0&format=json"
+    );
+}
+
+#[test]
+fn reads_settings_from_file() {
+    let config = load(
+        r#"
+        discord_token = "from-file"
+        poll_interval_secs = 30
+        poll_jitter_secs = 5
+        request_timeout_secs = 3
+        steam_api_url = "http://localhost:8080/"
+        news_count = 20
+        news_maxlength = 500
+        "#,
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(config.discord_token, "from-file");
+    assert_eq!(config.poll_interval, Duration::from_secs(30));
+    assert_eq!(config.poll_jitter, Duration::from_secs(5));
+    assert_eq!(config.request_timeout, Duration::from_secs(3));
+    assert_eq!(
+        news_url(&config),
+        "http://localhost:8080/ISteamNews/GetNewsForApp/v0002/?appid=570&count=20&maxlength=500&format=json"
+    );
+}
+
+#[test]
+fn environment_overrides_file() {
+    let config = load(
+        "discord_token = \"from-file\"\npoll_interval_secs = 30",
+        &[("DISCORD_TOKEN", "from-env"), ("POLL_INTERVAL_SECS", "90")],
+    )
+    .unwrap();
+
+    assert_eq!(config.discord_token, "from-env");
+    assert_eq!(config.poll_interval, Duration::from_secs(90));
+}
+
+#[test]
+fn rejects_invalid_settings() {
+    assert!(matches!(
+        load("", &[]),
+        Err(ConfigError::Missing("DISCORD_TOKEN"))
+    ));
+    assert!(matches!(
+        load(
+            "",
+            &[("DISCORD_TOKEN", "a"), ("POLL_INTERVAL_SECS", "soon")]
+        ),
+        Err(ConfigError::Env("POLL_INTERVAL_SECS", _))
+    ));
+    assert!(matches!(
+        load("poll_interval_secs = 0", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Invalid(..))
+    ));
+    assert!(matches!(
+        load("news_count = 500", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Invalid(..))
+    ));
+    assert!(matches!(
+        load(
+            "steam_api_url = \"ftp://mirror\"",
+            &[("DISCORD_TOKEN", "a")]
+        ),
+        Err(ConfigError::Invalid(..))
+    ));
+    assert!(matches!(
+        load("poll_interval = 5", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Parse(..))
+    ));
+}
+
+#[test]
+fn reads_patch_window() {
+    let config = load(
+        "patch_window_utc = \"22:00-02:00\"\npatch_window_poll_interval_secs = 15",
+        &[("DISCORD_TOKEN", "a"), ("MAX_BACKOFF_SECS", "600")],
+    )
+    .unwrap();
+
+    assert_eq!(
+        config
+            .patch_window
+            .map(|window| window.to_string())
+            .as_deref(),
+        Some("22:00-02:00")
+    );
+    assert_eq!(config.patch_window_poll_interval, Duration::from_secs(15));
+    assert_eq!(config.max_backoff, Duration::from_secs(600));
+
+    assert!(matches!(
+        load("patch_window_utc = \"evening\"", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Invalid(..))
+    ));
+    assert!(matches!(
+        load(
+            "",
+            &[("DISCORD_TOKEN", "a"), ("PATCH_WINDOW_UTC", "25:00-26:00")]
+        ),
+        Err(ConfigError::Env("PATCH_WINDOW_UTC", _))
+    ));
+    assert!(matches!(
+        load("max_backoff_secs = 10", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Invalid(..))
+    ));
+}
+
+#[test]
+fn reads_scan_concurrency() {
+    let config = load("", &[("DISCORD_TOKEN", "a")]).unwrap();
+    assert_eq!(config.scan_concurrency, 8);
+
+    let config = load(
+        "scan_concurrency = 4",
+        &[("DISCORD_TOKEN", "a"), ("SCAN_CONCURRENCY", "2")],
+    )
+    .unwrap();
+    assert_eq!(config.scan_concurrency, 2);
+
+    assert!(matches!(
+        load("scan_concurrency = 0", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Invalid(..))
+    ));
+    assert!(matches!(
+        load("", &[("DISCORD_TOKEN", "a"), ("SCAN_CONCURRENCY", "many")]),
+        Err(ConfigError::Env("SCAN_CONCURRENCY", _))
+    ));
+}
+
+#[test]
+fn reads_routes() {
+    let config = load(
+        r#"
+        [[routes]]
+        guild = 1
+        channel = 10
+        tags = ["patchnotes"]
+        restart_ping = true
+
+        [[routes]]
+        guild = 1
+        channel = 11
+        feeds = ["steam_community_announcements"]
+        title = "(?i)battle pass"
+        "#,
+        &[("DISCORD_TOKEN", "a")],
+    )
+    .unwrap();
+
+    let routes = &config.routes[&GuildId(1)];
+    assert_eq!(routes.len(), 2);
+    assert_eq!(routes[0].channel_id, ChannelId(10));
+    assert_eq!(routes[0].tags, ["patchnotes"]);
+    assert!(routes[0].restart_ping);
+    assert!(!routes[1].restart_ping);
+    assert!(routes[1]
+        .title
+        .as_ref()
+        .unwrap()
+        .is_match("The Battle Pass"));
+
+    assert!(matches!(
+        load(
+            "[[routes]]\nguild = 1\nchannel = 10\ntitle = \"(\"",
+            &[("DISCORD_TOKEN", "a")]
+        ),
+        Err(ConfigError::Invalid("route title", _))
+    ));
+    assert!(matches!(
+        load("[[routes]]\nguild = 1", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Parse(..))
+    ));
+}
+
+#[test]
+fn reads_announce_roles() {
+    let config = load(
+        "[[announce_roles]]\nguild = 1\nrole = 50\n\n[[announce_roles]]\nguild = 2\nrole = 60\nminor = true",
+        &[("DISCORD_TOKEN", "a")],
+    )
+    .unwrap();
+
+    assert_eq!(config.announce_roles[&GuildId(1)].role_id, RoleId(50));
+    assert!(!config.announce_roles[&GuildId(1)].minor);
+    assert!(config.announce_roles[&GuildId(2)].minor);
+    assert!(!config.announce_roles.contains_key(&GuildId(3)));
+}
+
+#[test]
+fn reads_crosspost_guilds() {
+    let config = load("crosspost_guilds = [1, 2]", &[("DISCORD_TOKEN", "a")]).unwrap();
+
+    assert!(config.crosspost_guilds.contains(&GuildId(1)));
+    assert!(config.crosspost_guilds.contains(&GuildId(2)));
+    assert!(!config.crosspost_guilds.contains(&GuildId(3)));
+}
+
+#[test]
+fn reads_webhooks() {
+    let config = load(
+        r#"
+        [[webhooks]]
+        url = "https://hooks.slack.com/services/abc"
+        kind = "slack"
+
+        [[webhooks]]
+        guild = 1
+        url = "https://discord.com/api/webhooks/1/abc"
+        kind = "discord"
+        username = "Patch Bot"
+        "#,
+        &[("DISCORD_TOKEN", "a")],
+    )
+    .unwrap();
+
+    assert_eq!(config.webhooks.len(), 2);
+    assert_eq!(config.webhooks[0].guild_id, None);
+    assert_eq!(config.webhooks[0].kind, WebhookKind::Slack);
+    assert_eq!(config.webhooks[1].guild_id, Some(GuildId(1)));
+    assert_eq!(
+        config.webhooks[1].kind,
+        WebhookKind::Discord {
+            username: Some(String::from("Patch Bot")),
+            avatar_url: None
+        }
+    );
+
+    let invalid = |toml| {
+        matches!(
+            load(toml, &[("DISCORD_TOKEN", "a")]),
+            Err(ConfigError::Invalid(..))
+        )
+    };
+    assert!(invalid("[[webhooks]]\nurl = \"https://a\"\nkind = \"irc\""));
+    assert!(invalid("[[webhooks]]\nurl = \"ftp://a\"\nkind = \"json\""));
+    assert!(invalid(
+        "[[webhooks]]\nurl = \"https://a\"\nkind = \"slack\"\nusername = \"x\""
+    ));
+}
+
+#[test]
+fn reads_game_detection() {
+    let config = load(
+        "game_application_ids = [42]\ngame_names = [\"^Dota 2 Test$\"]",
+        &[("DISCORD_TOKEN", "a")],
+    )
+    .unwrap();
+    assert!(config.game.matches(&Activity::playing("Dota 2 Test")));
+    assert!(!config.game.matches(&Activity::playing("Dota 2")));
+
+    let defaults = load("", &[("DISCORD_TOKEN", "a")]).unwrap();
+    assert!(defaults.game.matches(&Activity::playing("Dota 2")));
+
+    assert!(matches!(
+        load("game_names = [\"(\"]", &[("DISCORD_TOKEN", "a")]),
+        Err(ConfigError::Invalid("game name", _))
+    ));
+}
+
+#[test]
+fn reads_voice_notices() {
+    let config = load(
+        r#"
+        [[voice_notices]]
+        guild = 1
+
+        [[voice_notices]]
+        guild = 2
+        replace_ping = true
+        "#,
+        &[("DISCORD_TOKEN", "a")],
+    )
+    .unwrap();
+
+    assert!(!config.voice_notices[&GuildId(1)].replace_ping);
+    assert!(config.voice_notices[&GuildId(2)].replace_ping);
+    assert!(!config.voice_notices.contains_key(&GuildId(3)));
+}
+
+#[test]
+fn reads_ping_cooldowns() {
+    let config = load(
+        r#"
+        [[ping_cooldowns]]
+        guild = 1
+        minutes = 20
+        "#,
+        &[("DISCORD_TOKEN", "a")],
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.ping_cooldowns[&GuildId(1)],
+        Duration::from_secs(20 * 60)
+    );
+    assert!(!config.ping_cooldowns.contains_key(&GuildId(2)));
+
+    let zero = load(
+        r#"
+        [[ping_cooldowns]]
+        guild = 1
+        minutes = 0
+        "#,
+        &[("DISCORD_TOKEN", "a")],
+    );
+    assert!(matches!(
+        zero,
+        Err(ConfigError::Invalid("ping cooldown", _))
+    ));
+}
@@ -0,0 +1,36 @@
+use jenkins_bot::diff::{line_changes, render_diff, LineChange};
+
+#[test]
+fn finds_added_and_removed_lines() {
+    let old = "Anti-Mage\n- Blink range 925\nAxe\n- Armor 2";
+    let new = "Anti-Mage\n- Blink range 900\nAxe\n- Armor 2\nBane\n- Nightmare cooldown 22";
+
+    assert_eq!(
+        line_changes(old, new),
+        [
+            LineChange::Removed("- Blink range 925"),
+            LineChange::Added("- Blink range 900"),
+            LineChange::Added("Bane"),
+            LineChange::Added("- Nightmare cooldown 22"),
+        ]
+    );
+    assert!(line_changes(old, &format!("{}  \r\n", old)).is_empty());
+}
+
+#[test]
+fn renders_what_fits() {
+    let changes = [
+        LineChange::Removed("old line"),
+        LineChange::Added("new line"),
+        LineChange::Added("another new line"),
+    ];
+
+    assert_eq!(
+        render_diff(&changes, 100),
+        "```diff\n- old line\n+ new line\n+ another new line\n```"
+    );
+    assert_eq!(
+        render_diff(&changes, 25),
+        "```diff\n- old line\n+ new line\n```\n…and 1 more changed lines"
+    );
+}
@@ -0,0 +1,274 @@
+mod common;
+
+use common::{channel, message, snowflake, FakeDiscord};
+use jenkins_bot::ping_scanner::{PingScanner, ScanDepth, ScanProgress};
+use serenity::model::channel::ChannelType;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+
+const GUILD: u64 = 1;
+const ROLE: &str = "<@&42>";
+/// Base time for fixtures, since snowflakes can't represent anything before 2015.
+const T: i64 = 1_600_000_000;
+
+async fn find(
+    discord: &FakeDiscord,
+    scanner: &PingScanner,
+) -> Option<jenkins_bot::discord::MessageInfo> {
+    let mut errors = vec![];
+    let channels = scanner
+        .scannable_channels(discord, GuildId(GUILD), false, &mut errors)
+        .await
+        .unwrap();
+    scanner
+        .find_newest(discord, channels, ROLE, &ScanProgress::default())
+        .await
+}
+
+#[tokio::test]
+async fn finds_newest_ping_across_channels() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![
+            channel(1, ChannelType::Text, Some(snowflake(T + 5000))),
+            channel(2, ChannelType::Text, Some(snowflake(T + 5000))),
+        ],
+    );
+    discord.add_messages(vec![
+        message(1, T + 1000, 0, 100, "<@&42> anyone?"),
+        message(1, T + 5000, 0, 101, "gg"),
+        message(2, T + 3000, 0, 102, "<@&42> 5 stack"),
+        message(2, T + 4000, 0, 103, "<@&7> wrong role"),
+    ]);
+
+    let newest = find(&discord, &PingScanner::new(4)).await.unwrap();
+
+    assert_eq!(newest.channel_id, ChannelId(2));
+    assert_eq!(newest.author_id.0, 102);
+    assert_eq!(newest.timestamp, T + 3000);
+}
+
+#[tokio::test]
+async fn includes_active_threads() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![channel(1, ChannelType::Text, Some(snowflake(T + 1000)))],
+    );
+    discord.active_threads.insert(
+        GuildId(GUILD),
+        vec![channel(
+            2,
+            ChannelType::PublicThread,
+            Some(snowflake(T + 2000)),
+        )],
+    );
+    discord.add_messages(vec![
+        message(1, T + 1000, 0, 100, "<@&42>"),
+        message(2, T + 2000, 0, 101, "<@&42> in a thread"),
+    ]);
+
+    let newest = find(&discord, &PingScanner::new(4)).await.unwrap();
+
+    assert_eq!(newest.channel_id, ChannelId(2));
+}
+
+#[tokio::test]
+async fn includes_recently_archived_threads_when_asked() {
+    let now = chrono::Utc::now().timestamp();
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![channel(1, ChannelType::Text, Some(snowflake(now - 3600)))],
+    );
+    let mut recent = channel(2, ChannelType::PublicThread, Some(snowflake(now - 60)));
+    recent.archived_at = Some(now - 30);
+    let mut stale = channel(3, ChannelType::PublicThread, Some(snowflake(now - 30)));
+    stale.archived_at = Some(now - 60 * 60 * 24 * 30);
+    discord
+        .archived_threads
+        .insert(ChannelId(1), vec![recent, stale]);
+    discord.add_messages(vec![
+        message(1, now - 3600, 0, 100, "<@&42>"),
+        message(2, now - 60, 0, 101, "<@&42> archived"),
+        message(3, now - 30, 0, 102, "<@&42> long archived"),
+    ]);
+    let scanner = PingScanner::new(4);
+
+    let mut errors = vec![];
+    let without = scanner
+        .scannable_channels(&discord, GuildId(GUILD), false, &mut errors)
+        .await
+        .unwrap();
+    let with = scanner
+        .scannable_channels(&discord, GuildId(GUILD), true, &mut errors)
+        .await
+        .unwrap();
+
+    assert_eq!(without.len(), 1);
+    assert_eq!(
+        with.iter().map(|c| c.id).collect::<Vec<_>>(),
+        vec![ChannelId(1), ChannelId(2)]
+    );
+    let newest = scanner
+        .find_newest(&discord, with, ROLE, &ScanProgress::default())
+        .await
+        .unwrap();
+    assert_eq!(newest.channel_id, ChannelId(2));
+}
+
+#[tokio::test]
+async fn skips_channels_that_cannot_hold_messages() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![
+            channel(1, ChannelType::Text, Some(snowflake(T + 1000))),
+            channel(2, ChannelType::Directory, Some(snowflake(T + 2000))),
+            channel(3, ChannelType::Category, None),
+            channel(4, ChannelType::Text, None),
+        ],
+    );
+    discord.add_messages(vec![message(1, T + 1000, 0, 100, "<@&42>")]);
+
+    find(&discord, &PingScanner::new(4)).await.unwrap();
+
+    assert_eq!(discord.scanned_channels(), vec![ChannelId(1)]);
+}
+
+#[tokio::test]
+async fn finds_pings_in_voice_and_stage_chats() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![
+            channel(1, ChannelType::Text, Some(snowflake(T + 1000))),
+            channel(2, ChannelType::Voice, Some(snowflake(T + 2000))),
+            channel(3, ChannelType::Stage, Some(snowflake(T + 1500))),
+        ],
+    );
+    discord.add_messages(vec![
+        message(1, T + 1000, 0, 100, "<@&42>"),
+        message(2, T + 2000, 0, 101, "<@&42>"),
+        message(3, T + 1500, 0, 102, "<@&42>"),
+    ]);
+
+    let newest = find(&discord, &PingScanner::new(4)).await.unwrap();
+
+    assert_eq!(newest.channel_id, ChannelId(2));
+    assert_eq!(
+        discord.scanned_channels(),
+        vec![ChannelId(1), ChannelId(2), ChannelId(3)]
+    );
+}
+
+#[tokio::test]
+async fn stops_once_remaining_channels_are_older_than_the_ping() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![
+            channel(1, ChannelType::Text, Some(snowflake(T + 5000))),
+            channel(2, ChannelType::Text, Some(snowflake(T + 2000))),
+            channel(3, ChannelType::Text, Some(snowflake(T + 1000))),
+        ],
+    );
+    discord.add_messages(vec![
+        message(1, T + 4000, 0, 100, "<@&42>"),
+        message(1, T + 5000, 0, 100, "hi"),
+        message(2, T + 2000, 0, 101, "<@&42>"),
+        message(3, T + 1000, 0, 102, "<@&42>"),
+    ]);
+
+    let newest = find(&discord, &PingScanner::new(1)).await.unwrap();
+
+    assert_eq!(newest.channel_id, ChannelId(1));
+    assert_eq!(discord.scanned_channels(), vec![ChannelId(1)]);
+}
+
+#[tokio::test]
+async fn failing_channels_do_not_fail_the_scan() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![
+            channel(1, ChannelType::Text, Some(snowflake(T + 2000))),
+            channel(2, ChannelType::Text, Some(snowflake(T + 1000))),
+        ],
+    );
+    discord.forbidden.push(ChannelId(1));
+    discord.add_messages(vec![
+        message(1, T + 2000, 0, 100, "<@&42>"),
+        message(2, T + 1000, 0, 101, "<@&42>"),
+    ]);
+
+    let newest = find(&discord, &PingScanner::new(4)).await.unwrap();
+
+    assert_eq!(newest.channel_id, ChannelId(2));
+}
+
+#[tokio::test]
+async fn pages_back_through_history_up_to_depth() {
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![channel(1, ChannelType::Text, Some(snowflake(T + 10_000)))],
+    );
+    let mut messages = vec![message(1, T + 1000, 0, 100, "<@&42> old ping")];
+    messages.extend((0..250).map(|i| message(1, T + 2000 + i, 0, 101, "chatter")));
+    discord.add_messages(messages);
+
+    let shallow = find(&discord, &PingScanner::new(4)).await;
+    assert!(shallow.is_none());
+
+    let deep = PingScanner::new(4).depth(ScanDepth {
+        messages: 300,
+        since: None,
+    });
+    let newest = find(&discord, &deep).await.unwrap();
+    assert_eq!(newest.timestamp, T + 1000);
+    let befores: Vec<Option<MessageId>> = discord
+        .history_requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, before)| *before)
+        .collect();
+    // One request for the shallow scan, then three pages for the deep one
+    assert_eq!(befores.len(), 4);
+    assert_eq!(befores[1], None);
+    assert!(befores[2].is_some() && befores[3].is_some());
+}
+
+#[tokio::test]
+async fn does_not_search_past_since() {
+    let now = chrono::Utc::now().timestamp();
+    let mut discord = FakeDiscord::default();
+    discord.channels.insert(
+        GuildId(GUILD),
+        vec![
+            channel(1, ChannelType::Text, Some(snowflake(now - 60))),
+            channel(
+                2,
+                ChannelType::Text,
+                Some(snowflake(now - 60 * 60 * 24 * 10)),
+            ),
+        ],
+    );
+    discord.add_messages(vec![
+        message(1, now - 60, 0, 100, "chatter"),
+        message(1, now - 60 * 60 * 24 * 3, 0, 101, "<@&42> three days ago"),
+        message(2, now - 60 * 60 * 24 * 10, 0, 102, "<@&42> ten days ago"),
+    ]);
+    let scanner = |days| {
+        PingScanner::new(4).depth(ScanDepth {
+            messages: 1000,
+            since: Some(chrono::Utc::now() - chrono::Duration::days(days)),
+        })
+    };
+
+    assert!(find(&discord, &scanner(2)).await.is_none());
+    // The channel whose last message is older than `since` is never requested
+    assert_eq!(discord.scanned_channels(), vec![ChannelId(1)]);
+    assert_eq!(find(&discord, &scanner(5)).await.unwrap().author_id.0, 101);
+}
@@ -0,0 +1,88 @@
+use jenkins_bot::members::{
+    users_to_look_up, MemberCoverage, UserLookups, FAILED_LOOKUP_RETRY, MAX_REMEMBERED_LOOKUPS,
+};
+use serenity::model::id::{GuildId, UserId};
+use std::time::{Duration, Instant};
+
+fn coverage(member_count: u64, cached_members: usize) -> MemberCoverage {
+    MemberCoverage {
+        guild_id: GuildId(1),
+        member_count,
+        cached_members,
+        presences: 12,
+    }
+}
+
+#[test]
+fn reports_member_coverage() {
+    assert_eq!(
+        coverage(400, 100).to_string(),
+        "Guild 1: 100/400 members cached (25%), 12 presences"
+    );
+    assert_eq!(
+        coverage(0, 0).to_string(),
+        "Guild 1: 0/0 members cached (100%), 12 presences"
+    );
+}
+
+#[test]
+fn complete_once_every_member_is_cached() {
+    assert!(!coverage(400, 100).is_complete());
+    assert!(coverage(400, 400).is_complete());
+    // Members can join between the count and the chunks arriving
+    assert!(coverage(400, 401).is_complete());
+}
+
+#[test]
+fn remembers_failed_lookups_until_retry() {
+    let start = Instant::now();
+    let mut lookups = UserLookups::default();
+    assert!(lookups.should_look_up(UserId(1), start));
+
+    lookups.record(UserId(1), Some(true), start);
+    lookups.record(UserId(2), None, start);
+
+    assert_eq!(lookups.is_bot(UserId(1)), Some(true));
+    assert_eq!(lookups.is_bot(UserId(2)), None);
+    assert!(!lookups.should_look_up(UserId(1), start + FAILED_LOOKUP_RETRY));
+    assert!(!lookups.should_look_up(UserId(2), start + Duration::from_secs(60)));
+    assert!(lookups.should_look_up(UserId(2), start + FAILED_LOOKUP_RETRY));
+}
+
+#[test]
+fn forgets_the_oldest_lookups() {
+    let start = Instant::now();
+    let mut lookups = UserLookups::default();
+    for id in 0..=MAX_REMEMBERED_LOOKUPS as u64 {
+        lookups.record(UserId(id + 1), Some(false), start + Duration::from_secs(id));
+    }
+
+    assert_eq!(lookups.len(), MAX_REMEMBERED_LOOKUPS);
+    assert!(lookups.should_look_up(UserId(1), start));
+    assert_eq!(lookups.is_bot(UserId(2)), Some(false));
+}
+
+#[test]
+fn looks_up_users_the_cache_and_earlier_lookups_cant_place() {
+    let start = Instant::now();
+    let mut lookups = UserLookups::default();
+    lookups.record(UserId(3), Some(true), start);
+    lookups.record(UserId(4), None, start);
+    lookups.record(UserId(5), None, start - FAILED_LOOKUP_RETRY);
+    let presences = [
+        (UserId(6), None),
+        (UserId(1), Some(false)),
+        (UserId(2), None),
+        (UserId(3), None),
+        (UserId(4), None),
+        (UserId(5), None),
+        // The same user seen in another guild
+        (UserId(2), None),
+    ];
+
+    assert_eq!(
+        users_to_look_up(&presences, &lookups, start),
+        vec![UserId(2), UserId(5), UserId(6)]
+    );
+    assert!(users_to_look_up(&[], &lookups, start).is_empty());
+}
@@ -0,0 +1,52 @@
+use jenkins_bot::mentions::{chunk_mentions, join_list, mention_list};
+use serenity::model::id::UserId;
+
+#[test]
+fn joins_lists_in_english() {
+    assert_eq!(join_list::<&str>(&[]), "");
+    assert_eq!(join_list(&["Axe"]), "Axe");
+    assert_eq!(join_list(&["Axe", "Bane"]), "Axe and Bane");
+    assert_eq!(join_list(&["Axe", "Bane", "Lina"]), "Axe, Bane and Lina");
+    assert_eq!(
+        mention_list(&[UserId(1), UserId(2), UserId(3)]),
+        "<@1>, <@2> and <@3>"
+    );
+}
+
+#[test]
+fn chunks_only_when_over_the_limit() {
+    let users = [UserId(1), UserId(2), UserId(3)];
+    let render = |chunk: &[UserId]| format!("Hi {}", mention_list(chunk));
+
+    let messages = chunk_mentions(&users, 2000, |user| *user, render);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].content.as_deref(),
+        Some("Hi <@1>, <@2> and <@3>")
+    );
+    assert_eq!(messages[0].allowed_users.as_deref(), Some(&users[..]));
+
+    // "Hi <@1> and <@2>" is 16 characters
+    let messages = chunk_mentions(&users, 16, |user| *user, render);
+    let contents: Vec<&str> = messages
+        .iter()
+        .map(|message| message.content.as_deref().unwrap())
+        .collect();
+    assert_eq!(contents, ["Hi <@1> and <@2>", "Hi <@3>"]);
+    assert_eq!(messages[1].allowed_users.as_deref(), Some(&[UserId(3)][..]));
+}
+
+#[test]
+fn oversized_entries_are_sent_alone() {
+    let users = [UserId(1), UserId(2)];
+
+    let messages = chunk_mentions(&users, 3, |user| *user, mention_list);
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].content.as_deref(), Some("<@1>"));
+}
+
+#[test]
+fn nobody_to_mention_sends_nothing() {
+    assert!(chunk_mentions(&[], 2000, |user: &UserId| *user, |_| String::from("Hi")).is_empty());
+}
@@ -0,0 +1,80 @@
+use chrono::{FixedOffset, TimeZone, Utc};
+use jenkins_bot::party::{parse_start, Party, PARTY_SIZE};
+use jenkins_bot::user_prefs::{Timezone, UserPrefs};
+use serenity::model::id::UserId;
+
+#[test]
+fn starts_with_the_host() {
+    let party = Party::new(UserId(1), 9, None);
+    assert_eq!(party.joined, [UserId(1)]);
+    assert_eq!(party.slots, PARTY_SIZE);
+    assert!(!party.is_full());
+}
+
+#[test]
+fn joins_leaves_and_maybes() {
+    let mut party = Party::new(UserId(1), 5, None);
+
+    assert!(party.maybe(UserId(2)));
+    assert!(party.join(UserId(2)));
+    assert!(!party.join(UserId(2)));
+    assert_eq!(party.joined, [UserId(1), UserId(2)]);
+    assert!(party.maybe.is_empty());
+
+    assert!(party.maybe(UserId(2)));
+    assert_eq!(party.joined, [UserId(1)]);
+    assert_eq!(party.maybe, [UserId(2)]);
+
+    assert!(party.leave(UserId(2)));
+    assert!(!party.leave(UserId(2)));
+    assert!(party.maybe.is_empty());
+}
+
+#[test]
+fn fills_up() {
+    let mut party = Party::new(UserId(1), 3, None);
+    assert!(party.join(UserId(2)));
+    assert!(party.join(UserId(3)));
+    assert!(party.is_full());
+    assert!(!party.join(UserId(4)));
+    assert_eq!(party.joined.len(), 3);
+}
+
+#[test]
+fn describes_the_party() {
+    let mut party = Party::new(UserId(1), 5, Some(Utc.timestamp(1_700_000_000, 0)));
+    party.join(UserId(2));
+    party.maybe(UserId(3));
+    assert_eq!(
+        party.describe(),
+        "Starting: <t:1700000000:t> (<t:1700000000:R>)\n\
+         Players (2/5): <@1> and <@2>\n\
+         Maybe: <@3>"
+    );
+}
+
+#[test]
+fn parses_start_times_in_the_hosts_timezone() {
+    let now = Utc.ymd(2024, 5, 1).and_hms(10, 0, 0);
+    let prefs = UserPrefs {
+        timezone: Some(Timezone::Fixed(FixedOffset::east(10 * 3600))),
+        quiet_hours: None,
+    };
+
+    assert_eq!(parse_start("now", &prefs, now).unwrap(), None);
+    // 21:30 in UTC+10 is 11:30 UTC, still ahead today
+    assert_eq!(
+        parse_start("21:30", &prefs, now).unwrap(),
+        Some(Utc.ymd(2024, 5, 1).and_hms(11, 30, 0))
+    );
+    // 19:00 in UTC+10 has passed, so it's tomorrow's
+    assert_eq!(
+        parse_start("19:00", &prefs, now).unwrap(),
+        Some(Utc.ymd(2024, 5, 2).and_hms(9, 0, 0))
+    );
+    assert_eq!(
+        parse_start("19:00", &UserPrefs::default(), now).unwrap(),
+        Some(Utc.ymd(2024, 5, 1).and_hms(19, 0, 0))
+    );
+    assert!(parse_start("soon", &prefs, now).is_err());
+}
@@ -0,0 +1,69 @@
+use jenkins_bot::heroes::{find_hero, find_item};
+use jenkins_bot::patch_changes::extract_changes;
+
+#[test]
+fn finds_heroes_by_loose_names() {
+    assert_eq!(find_hero("anti mage"), Some("Anti-Mage"));
+    assert_eq!(find_hero("NATURES PROPHET"), Some("Nature's Prophet"));
+    assert_eq!(find_hero("Outworld Devourer"), Some("Outworld Destroyer"));
+    assert_eq!(find_hero("Blink Dagger"), None);
+    assert_eq!(find_hero(""), None);
+    assert_eq!(find_item("bkb"), None);
+    assert_eq!(find_item("black king bar"), Some("Black King Bar"));
+}
+
+#[test]
+fn extracts_hero_and_item_sections() {
+    let changes = extract_changes(
+        "[h2]General Updates[/h2]\n\
+         [list][*]Tormentor respawn time increased[/list]\n\
+         [h2]Item Updates[/h2]\n\
+         [b]Black King Bar[/b]\n\
+         [list][*]Duration reduced from 9 to 8[/list]\n\
+         [h2]Hero Updates[/h2]\n\
+         [b]Anti-Mage[/b]\n\
+         [list][*]Base armor increased by 1[*]Blink: Cooldown reduced by 1s[/list]\n\
+         [b]Axe[/b]\n\
+         [list][*]Strength gain reduced from 2.8 to 2.6[/list]",
+    );
+
+    assert_eq!(changes.hero_names(), ["Anti-Mage", "Axe"]);
+    assert_eq!(
+        changes.hero("Anti-Mage").unwrap().changes,
+        ["Base armor increased by 1", "Blink: Cooldown reduced by 1s"]
+    );
+    assert_eq!(
+        changes.hero("Axe").unwrap().changes,
+        ["Strength gain reduced from 2.8 to 2.6"]
+    );
+    assert_eq!(
+        changes.item("Black King Bar").unwrap().changes,
+        ["Duration reduced from 9 to 8"]
+    );
+    // General changes don't belong to anyone
+    assert_eq!(changes.items.len(), 1);
+}
+
+#[test]
+fn extracts_name_prefixed_lines() {
+    let changes = extract_changes(
+        "* Lina: Laguna Blade damage increased\n\
+         * Lion: Mana Drain now slows\n\
+         * Lina: Base intelligence increased by 2",
+    );
+
+    assert_eq!(changes.hero_names(), ["Lina", "Lion"]);
+    assert_eq!(
+        changes.hero("Lina").unwrap().changes,
+        [
+            "Laguna Blade damage increased",
+            "Base intelligence increased by 2"
+        ]
+    );
+}
+
+#[test]
+fn notes_without_heroes_have_no_changes() {
+    assert!(extract_changes("Fixed a crash when loading into a match.").is_empty());
+    assert!(extract_changes("").is_empty());
+}
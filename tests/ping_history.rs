@@ -0,0 +1,53 @@
+mod common;
+
+use common::message;
+use jenkins_bot::ping_cooldown::cooldown_notice;
+use jenkins_bot::ping_history::PingHistory;
+use serenity::model::id::{GuildId, UserId};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const T: i64 = 1_900_000_000;
+
+#[test]
+fn keeps_the_newest_ping() {
+    let mut history = PingHistory::default();
+    history.record(GuildId(1), message(10, T + 100, 0, 100, "<@&42>"));
+    history.record(GuildId(1), message(10, T, 0, 101, "<@&42>"));
+    history.record(GuildId(2), message(20, T, 0, 102, "<@&43>"));
+
+    assert_eq!(history.last(GuildId(1)).unwrap().author_id, UserId(100));
+    assert_eq!(history.last(GuildId(2)).unwrap().author_id, UserId(102));
+    assert!(history.last(GuildId(3)).is_none());
+}
+
+#[test]
+fn finds_pings_within_a_cooldown() {
+    let cooldown = Duration::from_secs(20 * 60);
+    let mut history = PingHistory::new(HashMap::from([(GuildId(1), cooldown)]));
+    assert_eq!(history.cooldown(GuildId(1)), Some(cooldown));
+    assert_eq!(history.cooldown(GuildId(2)), None);
+    assert!(history.within(GuildId(1), cooldown, T).is_none());
+
+    history.record(GuildId(1), message(10, T, 0, 100, "<@&42>"));
+    assert!(history.within(GuildId(1), cooldown, T + 60).is_some());
+    assert!(history
+        .within(GuildId(1), cooldown, T + 20 * 60 - 1)
+        .is_some());
+    assert!(history.within(GuildId(1), cooldown, T + 20 * 60).is_none());
+}
+
+#[test]
+fn links_the_last_ping_in_cooldown_notices() {
+    let last = message(10, T, 0, 100, "<@&42>");
+    assert_eq!(
+        cooldown_notice(GuildId(1), &last, Duration::from_secs(20 * 60)),
+        format!(
+            "@Dote was already pinged <t:{}:R> (https://discord.com/channels/1/10/{}). \
+             It can be pinged again <t:{}:R>.",
+            T,
+            last.id,
+            T + 20 * 60
+        )
+    );
+}
@@ -0,0 +1,65 @@
+use jenkins_bot::playing::{GameMatcher, GameStatus, DOTA_APPLICATION_ID};
+use regex::Regex;
+use serenity::model::gateway::Activity;
+use serenity::model::id::ApplicationId;
+
+fn rich_presence(details: Option<&str>, state: Option<&str>) -> Activity {
+    let mut activity = Activity::playing("Dota 2");
+    activity.details = details.map(String::from);
+    activity.state = state.map(String::from);
+    activity
+}
+
+#[test]
+fn only_playing_activities_count() {
+    let game = GameMatcher::default();
+
+    assert!(game.matches(&Activity::playing("Dota 2")));
+    assert!(game.matches(&Activity::playing("DOTA 2")));
+    assert!(!game.matches(&Activity::listening("Dota 2")));
+    assert!(!game.matches(&Activity::watching("Dota 2")));
+    assert!(!game.matches(&Activity::playing("I hate dota")));
+    assert!(!game.matches(&Activity::playing("Dota Underlords")));
+}
+
+#[test]
+fn matches_by_application_id() {
+    let game = GameMatcher::default();
+    let mut renamed = Activity::playing("Some launcher name");
+    renamed.application_id = Some(ApplicationId(DOTA_APPLICATION_ID));
+
+    assert!(game.matches(&renamed));
+}
+
+#[test]
+fn names_are_configurable() {
+    let game = GameMatcher::new(vec![], vec![Regex::new("(?i)^dota 2 \\(test\\)$").unwrap()]);
+
+    assert!(game.matches(&Activity::playing("Dota 2 (Test)")));
+    assert!(!game.matches(&Activity::playing("Dota 2")));
+}
+
+#[test]
+fn reads_rich_presence_status() {
+    let game = GameMatcher::default();
+    let status = |activity: Activity| game.status(&[activity]);
+
+    assert_eq!(
+        status(rich_presence(Some("Playing as Axe"), Some("Ranked"))),
+        Some(GameStatus::InMatch)
+    );
+    assert_eq!(
+        status(rich_presence(None, Some("Finding a Match"))),
+        Some(GameStatus::InLobby)
+    );
+    assert_eq!(
+        status(rich_presence(Some("Spectating a match"), None)),
+        Some(GameStatus::Spectating)
+    );
+    assert_eq!(
+        status(rich_presence(Some("Main Menu"), None)),
+        Some(GameStatus::MainMenu)
+    );
+    assert_eq!(status(rich_presence(None, None)), Some(GameStatus::Unknown));
+    assert_eq!(status(Activity::playing("Deadlock")), None);
+}
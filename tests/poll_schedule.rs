@@ -0,0 +1,88 @@
+use chrono::{NaiveTime, TimeZone, Utc};
+use jenkins_bot::config::Config;
+use jenkins_bot::poll_schedule::{PollSchedule, TimeWindow};
+use std::time::Duration;
+
+fn config(toml: &str) -> Config {
+    Config::from_toml(toml, |name| match name {
+        "DISCORD_TOKEN" => Some(String::from("a")),
+        _ => None,
+    })
+    .unwrap()
+}
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms(hour, minute, 0)
+}
+
+#[test]
+fn windows_can_wrap_past_midnight() {
+    let day: TimeWindow = "17:00-21:00".parse().unwrap();
+    assert!(day.contains(time(17, 0)));
+    assert!(day.contains(time(20, 59)));
+    assert!(!day.contains(time(21, 0)));
+    assert!(!day.contains(time(3, 0)));
+
+    let night: TimeWindow = "22:00-02:00".parse().unwrap();
+    assert!(night.contains(time(23, 30)));
+    assert!(night.contains(time(1, 0)));
+    assert!(!night.contains(time(2, 0)));
+    assert!(!night.contains(time(12, 0)));
+
+    assert!("22:00".parse::<TimeWindow>().is_err());
+    assert!("10pm-2am".parse::<TimeWindow>().is_err());
+}
+
+#[test]
+fn polls_faster_inside_patch_window() {
+    let schedule = PollSchedule::new(&config(
+        "patch_window_utc = \"17:00-21:00\"\npatch_window_poll_interval_secs = 15",
+    ));
+
+    let inside = Utc.ymd(2022, 7, 20).and_hms(18, 0, 0);
+    let outside = Utc.ymd(2022, 7, 20).and_hms(9, 0, 0);
+    assert_eq!(schedule.base_delay(inside), Duration::from_secs(15));
+    assert_eq!(schedule.base_delay(outside), Duration::from_secs(60));
+}
+
+#[test]
+fn backs_off_exponentially_until_success() {
+    let mut schedule = PollSchedule::new(&config("max_backoff_secs = 300"));
+    let now = Utc.ymd(2022, 7, 20).and_hms(9, 0, 0);
+
+    schedule.record_failure(None);
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(120));
+    schedule.record_failure(None);
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(240));
+    schedule.record_failure(None);
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(300));
+    for _ in 0..100 {
+        schedule.record_failure(None);
+    }
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(300));
+
+    schedule.record_success();
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(60));
+}
+
+#[test]
+fn honours_retry_after() {
+    let mut schedule = PollSchedule::new(&config("max_backoff_secs = 300"));
+    let now = Utc.ymd(2022, 7, 20).and_hms(9, 0, 0);
+
+    schedule.record_failure(Some(Duration::from_secs(600)));
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(600));
+    schedule.record_failure(Some(Duration::from_secs(5)));
+    assert_eq!(schedule.base_delay(now), Duration::from_secs(240));
+}
+
+#[test]
+fn jitter_stays_in_bounds() {
+    let schedule = PollSchedule::new(&config("poll_jitter_secs = 5"));
+    let now = Utc.ymd(2022, 7, 20).and_hms(9, 0, 0);
+
+    for _ in 0..50 {
+        let delay = schedule.next_delay(now);
+        assert!(delay >= Duration::from_secs(60) && delay <= Duration::from_secs(65));
+    }
+}
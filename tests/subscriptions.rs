@@ -0,0 +1,67 @@
+use jenkins_bot::subscriptions::Subscriptions;
+use serenity::model::id::{GuildId, UserId};
+
+#[test]
+fn subscribes_per_guild_and_user() {
+    let mut subscriptions = Subscriptions::in_memory();
+    assert!(subscriptions
+        .subscribe(GuildId(1), UserId(10), "Axe")
+        .unwrap());
+    assert!(subscriptions
+        .subscribe(GuildId(1), UserId(10), "Bane")
+        .unwrap());
+    assert!(!subscriptions
+        .subscribe(GuildId(1), UserId(10), "Axe")
+        .unwrap());
+    subscriptions
+        .subscribe(GuildId(1), UserId(11), "Lina")
+        .unwrap();
+    subscriptions
+        .subscribe(GuildId(2), UserId(12), "Axe")
+        .unwrap();
+
+    assert_eq!(
+        subscriptions.heroes(GuildId(1), UserId(10)),
+        ["Axe", "Bane"]
+    );
+    assert_eq!(
+        subscriptions.subscribers(GuildId(1), &["Axe", "Lina", "Zeus"]),
+        [
+            (UserId(10), vec![String::from("Axe")]),
+            (UserId(11), vec![String::from("Lina")])
+        ]
+    );
+    assert!(subscriptions.subscribers(GuildId(3), &["Axe"]).is_empty());
+}
+
+#[test]
+fn unsubscribes() {
+    let mut subscriptions = Subscriptions::in_memory();
+    subscriptions
+        .subscribe(GuildId(1), UserId(10), "Axe")
+        .unwrap();
+
+    assert!(!subscriptions
+        .unsubscribe(GuildId(1), UserId(10), "Bane")
+        .unwrap());
+    assert!(subscriptions
+        .unsubscribe(GuildId(1), UserId(10), "Axe")
+        .unwrap());
+    assert!(subscriptions.heroes(GuildId(1), UserId(10)).is_empty());
+    assert!(subscriptions.subscribers(GuildId(1), &["Axe"]).is_empty());
+}
+
+#[test]
+fn saves_and_loads() {
+    let path = std::env::temp_dir().join(format!("subscriptions-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut subscriptions = Subscriptions::load(&path).unwrap();
+    subscriptions
+        .subscribe(GuildId(1), UserId(10), "Axe")
+        .unwrap();
+
+    let loaded = Subscriptions::load(&path).unwrap();
+    assert_eq!(loaded.heroes(GuildId(1), UserId(10)), ["Axe"]);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,191 @@
+use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use jenkins_bot::poll_schedule::TimeWindow;
+use jenkins_bot::user_prefs::{
+    format_utc_offset, parse_utc_offset, PrefsStore, Timezone, UserPrefs,
+};
+use serenity::model::id::UserId;
+
+fn hours(hours: i32) -> FixedOffset {
+    FixedOffset::east(hours * 3600)
+}
+
+#[test]
+fn parses_utc_offsets() {
+    assert_eq!(parse_utc_offset("UTC").unwrap(), hours(0));
+    assert_eq!(parse_utc_offset("utc+10").unwrap(), hours(10));
+    assert_eq!(parse_utc_offset("GMT-3").unwrap(), hours(-3));
+    assert_eq!(
+        parse_utc_offset("+05:30").unwrap(),
+        FixedOffset::east(5 * 3600 + 30 * 60)
+    );
+    assert_eq!(
+        parse_utc_offset("UTC-0330").unwrap(),
+        FixedOffset::west(3 * 3600 + 30 * 60)
+    );
+
+    assert!(parse_utc_offset("Europe/Berlin").is_err());
+    assert!(parse_utc_offset("UTC+15").is_err());
+    assert!(parse_utc_offset("UTC+5:60").is_err());
+}
+
+#[test]
+fn rejects_non_ascii_offsets() {
+    assert!(parse_utc_offset("é").is_err());
+    assert!(parse_utc_offset("+1é1").is_err());
+    assert!(parse_utc_offset("UTC+１０").is_err());
+    assert!("é".parse::<Timezone>().is_err());
+    assert!("+1é1".parse::<Timezone>().is_err());
+}
+
+#[test]
+fn parses_timezones() {
+    assert_eq!(
+        "Europe/Berlin".parse::<Timezone>().unwrap().to_string(),
+        "Europe/Berlin"
+    );
+    assert_eq!(
+        "UTC+10".parse::<Timezone>().unwrap(),
+        Timezone::Fixed(hours(10))
+    );
+    assert!("Mars/Olympus_Mons".parse::<Timezone>().is_err());
+}
+
+#[test]
+fn named_timezones_follow_daylight_saving() {
+    let berlin: Timezone = "Europe/Berlin".parse().unwrap();
+    assert_eq!(
+        berlin.offset_at(Utc.ymd(2024, 1, 15).and_hms(12, 0, 0)),
+        hours(1)
+    );
+    assert_eq!(
+        berlin.offset_at(Utc.ymd(2024, 7, 1).and_hms(12, 0, 0)),
+        hours(2)
+    );
+    // 02:30 doesn't exist on the night clocks go forward, so it's taken as 03:30 CEST
+    assert_eq!(
+        berlin.to_utc(NaiveDate::from_ymd(2024, 3, 31).and_hms(2, 30, 0)),
+        Utc.ymd(2024, 3, 31).and_hms(1, 30, 0)
+    );
+    // 02:30 happens twice the night they go back, and the first is taken
+    assert_eq!(
+        berlin.to_utc(NaiveDate::from_ymd(2024, 10, 27).and_hms(2, 30, 0)),
+        Utc.ymd(2024, 10, 27).and_hms(0, 30, 0)
+    );
+}
+
+#[test]
+fn formats_utc_offsets() {
+    assert_eq!(format_utc_offset(hours(0)), "UTC");
+    assert_eq!(format_utc_offset(hours(10)), "UTC+10:00");
+    assert_eq!(
+        format_utc_offset(FixedOffset::west(3 * 3600 + 30 * 60)),
+        "UTC-03:30"
+    );
+}
+
+fn quiet(start: u32, end: u32, offset: i32) -> UserPrefs {
+    UserPrefs {
+        timezone: Some(Timezone::Fixed(hours(offset))),
+        quiet_hours: Some(TimeWindow {
+            start: NaiveTime::from_hms(start, 0, 0),
+            end: NaiveTime::from_hms(end, 0, 0),
+        }),
+    }
+}
+
+#[test]
+fn quiet_hours_end_in_the_users_timezone() {
+    // 22:00-08:00 in UTC+10 is 12:00-22:00 UTC
+    let prefs = quiet(22, 8, 10);
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 5, 1).and_hms(13, 0, 0)),
+        Some(Utc.ymd(2024, 5, 1).and_hms(22, 0, 0))
+    );
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 5, 1).and_hms(21, 59, 0)),
+        Some(Utc.ymd(2024, 5, 1).and_hms(22, 0, 0))
+    );
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 5, 1).and_hms(22, 0, 0)),
+        None
+    );
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 5, 1).and_hms(11, 0, 0)),
+        None
+    );
+}
+
+#[test]
+fn quiet_hours_across_midnight() {
+    let prefs = quiet(23, 7, 0);
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 5, 1).and_hms(23, 30, 0)),
+        Some(Utc.ymd(2024, 5, 2).and_hms(7, 0, 0))
+    );
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 5, 2).and_hms(3, 0, 0)),
+        Some(Utc.ymd(2024, 5, 2).and_hms(7, 0, 0))
+    );
+    assert_eq!(UserPrefs::default().quiet_until(Utc::now()), None);
+}
+
+#[test]
+fn quiet_hours_in_a_named_timezone_move_with_daylight_saving() {
+    let prefs = UserPrefs {
+        timezone: Some("Europe/Berlin".parse().unwrap()),
+        ..quiet(22, 8, 0)
+    };
+    // 08:00 is 07:00 UTC in winter and 06:00 UTC in summer
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 1, 15).and_hms(22, 0, 0)),
+        Some(Utc.ymd(2024, 1, 16).and_hms(7, 0, 0))
+    );
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 7, 1).and_hms(21, 0, 0)),
+        Some(Utc.ymd(2024, 7, 2).and_hms(6, 0, 0))
+    );
+    assert_eq!(
+        prefs.quiet_until(Utc.ymd(2024, 7, 1).and_hms(6, 30, 0)),
+        None
+    );
+}
+
+#[test]
+fn formats_times_in_the_users_timezone() {
+    let prefs = quiet(22, 8, 10);
+    assert_eq!(
+        prefs.format_time(Utc.ymd(2024, 5, 1).and_hms(11, 30, 0)),
+        "2024-05-01 21:30 UTC+10:00"
+    );
+
+    let prefs = UserPrefs {
+        timezone: Some("Europe/Berlin".parse().unwrap()),
+        quiet_hours: None,
+    };
+    assert_eq!(
+        prefs.format_time(Utc.ymd(2024, 7, 1).and_hms(11, 30, 0)),
+        "2024-07-01 13:30 Europe/Berlin"
+    );
+}
+
+#[test]
+fn saves_and_loads() {
+    let path = std::env::temp_dir().join(format!("user-prefs-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut store = PrefsStore::load(&path).unwrap();
+    store.set(UserId(10), quiet(22, 8, 10)).unwrap();
+    store.set(UserId(11), quiet(1, 2, 0)).unwrap();
+    store.set(UserId(11), UserPrefs::default()).unwrap();
+    let berlin = UserPrefs {
+        timezone: Some("Europe/Berlin".parse().unwrap()),
+        quiet_hours: None,
+    };
+    store.set(UserId(12), berlin).unwrap();
+
+    let loaded = PrefsStore::load(&path).unwrap();
+    assert_eq!(loaded.get(UserId(10)), quiet(22, 8, 10));
+    assert_eq!(loaded.get(UserId(11)), UserPrefs::default());
+    assert_eq!(loaded.get(UserId(12)), berlin);
+    std::fs::remove_file(&path).unwrap();
+}
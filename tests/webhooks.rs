@@ -0,0 +1,117 @@
+mod common;
+
+use common::{article, at, FakeBuilds, FakeDiscord, FakeNews, FakeSink};
+use jenkins_bot::check_updates::UpdateChecker;
+use jenkins_bot::discord::Embed;
+use jenkins_bot::webhooks::{Webhook, WebhookKind};
+use serde_json::json;
+use serenity::model::id::GuildId;
+use std::collections::HashMap;
+
+fn embed() -> Embed {
+    Embed {
+        title: String::from("7.33"),
+        author: Some(String::from("Valve")),
+        description: String::from("See [here](https://dota2.com/patches/7.33) for more."),
+        timestamp: Some(at(1_600_000_000)),
+        color: 0x123456,
+    }
+}
+
+fn webhook(kind: WebhookKind) -> Webhook {
+    Webhook {
+        guild_id: None,
+        url: String::from("https://hooks.example.com/abc"),
+        kind,
+    }
+}
+
+#[test]
+fn discord_payload_uses_custom_identity() {
+    let payload = webhook(WebhookKind::Discord {
+        username: Some(String::from("Patch Bot")),
+        avatar_url: None,
+    })
+    .payload(&embed());
+
+    assert_eq!(
+        payload,
+        json!({
+            "username": "Patch Bot",
+            "embeds": [{
+                "title": "7.33",
+                "description": "See [here](https://dota2.com/patches/7.33) for more.",
+                "color": 0x123456,
+                "author": { "name": "Valve" },
+                "timestamp": "2020-09-13T12:26:40+00:00",
+            }],
+        })
+    );
+}
+
+#[test]
+fn json_payload_is_flat() {
+    assert_eq!(
+        webhook(WebhookKind::Json).payload(&embed()),
+        json!({
+            "title": "7.33",
+            "author": "Valve",
+            "description": "See [here](https://dota2.com/patches/7.33) for more.",
+            "timestamp": "2020-09-13T12:26:40+00:00",
+        })
+    );
+}
+
+#[test]
+fn slack_payload_converts_links() {
+    assert_eq!(
+        webhook(WebhookKind::Slack).payload(&embed()),
+        json!({ "text": "*7.33*\nSee <https://dota2.com/patches/7.33|here> for more." })
+    );
+}
+
+#[tokio::test]
+async fn sinks_get_their_guilds_announcements() {
+    let news = FakeNews::with(vec![article("1", "7.33", at(2000), &["patchnotes"])]);
+    let mut discord = FakeDiscord::default();
+    discord.presences.insert(GuildId(1), vec![]);
+    discord.presences.insert(GuildId(2), vec![]);
+    let channels: HashMap<GuildId, [u64; 2]> = [(GuildId(1), [10, 11])].into_iter().collect();
+    let global = FakeSink::default();
+    let guild_one = FakeSink::default();
+    let guild_two = FakeSink::default();
+    let mut checker = UpdateChecker::new(at(1000), channels)
+        .sink(None, global.clone())
+        .sink(Some(GuildId(1)), guild_one.clone())
+        .sink(Some(GuildId(2)), guild_two.clone());
+
+    checker.poll(&news, &discord).await.unwrap();
+
+    assert_eq!(global.titles(), ["7.33"]);
+    assert_eq!(guild_one.titles(), ["7.33"]);
+    // Guild 2 has no channels or routes, so nothing is announced for it
+    assert!(guild_two.titles().is_empty());
+
+    let builds = FakeBuilds::with(1);
+    checker.poll_build(&builds, &discord).await.unwrap();
+    builds.set(2);
+    checker.poll_build(&builds, &discord).await.unwrap();
+    // Within the same update window as the patch notes, so nothing new
+    assert_eq!(global.titles().len(), 1);
+
+    builds.set(3);
+    checker.poll_build(&builds, &discord).await.unwrap();
+    let build_notice = "Dota client updated (build 2 → 3)";
+    assert_eq!(global.titles(), ["7.33", build_notice]);
+    assert_eq!(guild_one.titles(), ["7.33", build_notice]);
+    assert!(guild_two.titles().is_empty());
+
+    let mut edited = article("1", "7.33", at(2000), &["patchnotes"]);
+    edited.contents = String::from("Axe");
+    news.set(vec![edited]);
+    checker.poll(&news, &discord).await.unwrap();
+    let edit_notice = "Updated patch notes: 7.33";
+    assert_eq!(global.titles(), ["7.33", build_notice, edit_notice]);
+    assert_eq!(guild_one.titles(), ["7.33", build_notice, edit_notice]);
+    assert!(guild_two.titles().is_empty());
+}